@@ -0,0 +1,540 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Error};
+use colored::Colorize;
+use toml::{Table, Value};
+
+use crate::checksum::Algorithm;
+use crate::config::config;
+use crate::error::JargoError;
+use crate::lockfile::Lockfile;
+use crate::version::Version;
+
+/// the lock file in a directory, and the extension of those of the directories in the cache
+const LOCK_FILE: &str = ".lock";
+
+/// the file in the directory of an artifact that is touched every time it is used
+const USED_FILE: &str = ".used";
+
+/// the extension of files that are being written
+pub(crate) const PART: &str = "part";
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Writes a file in the cache under a temporary name and renames it when it's complete, so that
+/// another jargo process, or the next run after an interrupted one, never sees half a file.
+pub(crate) fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = path.as_ref();
+    let mut part = path.as_os_str().to_owned();
+    part.push(format!(
+        ".{}-{}.{}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        PART
+    ));
+    let part = PathBuf::from(part);
+    let result = (|| {
+        let mut file = File::create(&part)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&part, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&part);
+    }
+    result
+}
+
+/// An exclusive lock on a directory in the cache, like that of an artifact, so that only one
+/// jargo process downloads and verifies it at a time. Released when it's dropped.
+#[derive(Debug)]
+pub(crate) struct CacheLock {
+    _file: File,
+}
+
+impl CacheLock {
+    /// waits until no other process has the lock
+    pub(crate) fn acquire(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let config = config();
+        let path = lock_file(
+            Path::new(&config.cache_location),
+            &Path::new(&config.user_home).join("jargo/locks"),
+            dir,
+        );
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        file.lock()?;
+        // after the lock, because it may have been removed while waiting for it
+        fs::create_dir_all(dir)?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// The lock file of a directory in the cache is kept under `locks`, so that `jargo cache clean`
+/// and `prune` don't remove it with the directory while another process waits for it.
+fn lock_file(cache: &Path, locks: &Path, dir: &Path) -> PathBuf {
+    match dir.strip_prefix(cache) {
+        Ok(relative) => {
+            let mut file = locks.join(relative).into_os_string();
+            file.push(LOCK_FILE);
+            PathBuf::from(file)
+        }
+        Err(_) => dir.join(LOCK_FILE),
+    }
+}
+
+/// records that the artifact is used now, for `jargo cache prune`
+pub(crate) fn stamp(artifact_dir: impl AsRef<Path>) -> io::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(artifact_dir.as_ref().join(USED_FILE))?;
+    file.set_modified(SystemTime::now())
+}
+
+/// ~/jargo/lockfiles.toml, with the Jargo.lock files that were built with and when
+fn lockfiles_file() -> PathBuf {
+    Path::new(&config().user_home).join("jargo/lockfiles.toml")
+}
+
+/// Records that the artifacts in this Jargo.lock are used now, so that `jargo cache prune`
+/// keeps them
+pub(crate) fn record_lockfile(lockfile: &Path) -> Result<(), Error> {
+    let file = lockfiles_file();
+    let _lock = CacheLock::acquire(file.parent().unwrap_or(Path::new(".")))?;
+    let mut lockfiles = read_lockfiles(&file)?;
+    let lockfile = fs::canonicalize(lockfile)?;
+    lockfiles.insert(
+        lockfile.to_string_lossy().into(),
+        Value::Integer(seconds(SystemTime::now())),
+    );
+    write_lockfiles(&file, &lockfiles)
+}
+
+fn read_lockfiles(file: &Path) -> Result<Table, Error> {
+    if !file.exists() {
+        return Ok(Table::new());
+    }
+    fs::read_to_string(file)?
+        .parse::<Table>()
+        .map_err(|e| anyhow!("{} is not valid: {}", file.display(), e.message()))
+}
+
+fn write_lockfiles(file: &Path, lockfiles: &Table) -> Result<(), Error> {
+    let contents = format!(
+        "# the Jargo.lock files that jargo built with, and when, for `jargo cache prune`\n{}",
+        toml::to_string(lockfiles)?
+    );
+    write_atomic(file, contents.as_bytes())?;
+    Ok(())
+}
+
+fn seconds(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// the directory of an artifact in the cache, with its coordinates
+#[derive(Debug)]
+struct Cached {
+    group: String,
+    name: String,
+    version: String,
+    dir: PathBuf,
+    /// in bytes
+    size: u64,
+    /// the access stamp, or when it was downloaded
+    last_used: SystemTime,
+}
+
+impl Cached {
+    fn coordinates(&self) -> String {
+        format!("{}:{}:{}", self.group, self.name, self.version)
+    }
+
+    fn days_unused(&self) -> u64 {
+        let unused = SystemTime::now()
+            .duration_since(self.last_used)
+            .unwrap_or_default();
+        unused.as_secs() / DAY.as_secs()
+    }
+}
+
+/// every directory with files is an artifact, at group/path/name/version
+fn cached_artifacts() -> Result<Vec<Cached>, Error> {
+    let root = PathBuf::from(&config().cache_location);
+    let mut cached = vec![];
+    if root.exists() {
+        find_artifacts(&root, &root, &mut cached)?;
+    }
+    // 1.9 before 1.10
+    cached.sort_by_cached_key(|c| (c.group.clone(), c.name.clone(), Version::new(&c.version)));
+    Ok(cached)
+}
+
+fn find_artifacts(root: &Path, dir: &Path, cached: &mut Vec<Cached>) -> Result<(), Error> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            find_artifacts(root, &entry.path(), cached)?;
+        } else {
+            files.push(entry);
+        }
+    }
+    let parts: Vec<String> = dir
+        .strip_prefix(root)?
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    // the directory above the versions only has the maven-metadata.xml for version ranges
+    let has_pom = files
+        .iter()
+        .any(|f| f.path().extension().is_some_and(|e| e == "pom"));
+    if !has_pom || parts.len() < 3 {
+        return Ok(());
+    }
+    let (mut size, mut last_used) = (0, UNIX_EPOCH);
+    for file in files {
+        let metadata = file.metadata()?;
+        size += metadata.len();
+        last_used = last_used.max(metadata.modified()?);
+    }
+    if let Ok(stamp) = fs::metadata(dir.join(USED_FILE)).and_then(|m| m.modified()) {
+        last_used = stamp;
+    }
+    let n = parts.len();
+    cached.push(Cached {
+        group: parts[..n - 2].join("."),
+        name: parts[n - 2].clone(),
+        version: parts[n - 1].clone(),
+        dir: dir.to_path_buf(),
+        size,
+        last_used,
+    });
+    Ok(())
+}
+
+/// like 1.5 MiB
+fn human(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// `jargo cache list`: the cached artifacts with their size and when they were last used
+pub fn list() -> Result<(), Error> {
+    for cached in cached_artifacts()? {
+        let used = match cached.days_unused() {
+            0 => "used today".into(),
+            1 => "used yesterday".into(),
+            days => format!("used {} days ago", days),
+        };
+        println!(
+            "{:<60} {:>10}  {}",
+            cached.coordinates(),
+            human(cached.size),
+            used
+        );
+    }
+    Ok(())
+}
+
+/// `jargo cache size`: the number of artifacts and the space they take
+pub fn size() -> Result<(), Error> {
+    let cached = cached_artifacts()?;
+    println!(
+        "{} {} artifacts, {} in {}",
+        "Cache".green(),
+        cached.len(),
+        human(cached.iter().map(|c| c.size).sum()),
+        config().cache_location
+    );
+    Ok(())
+}
+
+/// `jargo cache verify`: checks every cached file with the strongest checksum that was stored
+/// with it. A file that doesn't match is removed, so that it is downloaded again by the next
+/// build, and the first one is returned as the error.
+pub fn verify() -> Result<(), Error> {
+    let (mut verified, mut unverified) = (0, 0);
+    let mut corrupt: Vec<JargoError> = vec![];
+    for cached in cached_artifacts()? {
+        let _lock = CacheLock::acquire(&cached.dir)?;
+        for entry in fs::read_dir(&cached.dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if !(name.ends_with(".jar") || name.ends_with(".pom")) {
+                continue;
+            }
+            let Some((algorithm, expected)) = stored_checksum(&path)? else {
+                unverified += 1;
+                continue;
+            };
+            let actual = algorithm.hash(&fs::read(&path)?);
+            if actual == expected {
+                verified += 1;
+            } else {
+                println!("{} {}", "Removed corrupt".red().bold(), path.display());
+                fs::remove_file(&path)?;
+                corrupt.push(JargoError::Checksum {
+                    url: path.to_string_lossy().into(),
+                    algorithm: algorithm.extension().into(),
+                    file: path.clone(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    println!(
+        "{} {} files, {} without a checksum, {} corrupt",
+        "Verified".green(),
+        verified,
+        unverified,
+        corrupt.len()
+    );
+    match corrupt.into_iter().next() {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
+}
+
+/// the strongest checksum next to the file, with its algorithm
+pub(crate) fn stored_checksum(file: &Path) -> Result<Option<(Algorithm, String)>, Error> {
+    for algorithm in Algorithm::STRONGEST_FIRST {
+        let mut checksum_file = file.as_os_str().to_owned();
+        checksum_file.push(format!(".{}", algorithm.extension()));
+        let checksum_file = PathBuf::from(checksum_file);
+        if checksum_file.exists() {
+            let expected = crate::checksum::parse(&fs::read(checksum_file)?);
+            return Ok(Some((algorithm, expected)));
+        }
+    }
+    Ok(None)
+}
+
+/// `jargo cache prune`: removes the artifacts that were not used in the last `days`, and that
+/// are not in a Jargo.lock that was built with in that time. Also forgets the Jargo.lock files
+/// that were not built with in that time.
+pub fn prune(days: u64) -> Result<(), Error> {
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(days.saturating_mul(DAY.as_secs())))
+        .unwrap_or(UNIX_EPOCH);
+    let file = lockfiles_file();
+    let mut keep = BTreeSet::new();
+    {
+        let _lock = CacheLock::acquire(file.parent().unwrap_or(Path::new(".")))?;
+        let mut lockfiles = read_lockfiles(&file)?;
+        lockfiles.retain(|lockfile, used| {
+            used.as_integer().unwrap_or_default() >= seconds(cutoff) && Path::new(lockfile).exists()
+        });
+        for lockfile in lockfiles.keys() {
+            keep.extend(Lockfile::read(Path::new(lockfile))?.artifacts);
+        }
+        write_lockfiles(&file, &lockfiles)?;
+    }
+
+    let (mut removed, mut freed) = (0, 0);
+    for cached in cached_artifacts()? {
+        let coordinates = (
+            cached.group.clone(),
+            cached.name.clone(),
+            cached.version.clone(),
+        );
+        if cached.last_used >= cutoff || keep.contains(&coordinates) {
+            continue;
+        }
+        let lock = CacheLock::acquire(&cached.dir)?;
+        fs::remove_dir_all(&cached.dir)?;
+        drop(lock);
+        remove_empty_parents(&cached.dir)?;
+        println!("{} {}", "Removed".green(), cached.coordinates());
+        removed += 1;
+        freed += cached.size;
+    }
+    println!(
+        "{} {} artifacts, {} freed",
+        "Pruned".green(),
+        removed,
+        human(freed)
+    );
+    Ok(())
+}
+
+/// `jargo cache clean`: removes everything, or the artifacts of a group, group:name or
+/// group:name:version
+pub fn clean(artifact: Option<&str>) -> Result<(), Error> {
+    let root = PathBuf::from(&config().cache_location);
+    let dir = artifact_dir(&root, artifact)?;
+    if !dir.exists() {
+        println!(
+            "{} nothing cached for {}",
+            "Clean".green(),
+            artifact.unwrap_or("jargo")
+        );
+        return Ok(());
+    }
+    let cached: Vec<_> = cached_artifacts()?
+        .into_iter()
+        .filter(|c| c.dir.starts_with(&dir))
+        .collect();
+    let size: u64 = cached.iter().map(|c| c.size).sum();
+    // nothing is removed while another process downloads it
+    let locks = cached
+        .iter()
+        .map(|c| CacheLock::acquire(&c.dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    fs::remove_dir_all(&dir)?;
+    drop(locks);
+    if dir == root {
+        fs::create_dir_all(&root)?;
+    } else {
+        remove_empty_parents(&dir)?;
+    }
+    println!(
+        "{} {}, {} freed",
+        "Removed".green(),
+        artifact.unwrap_or(&config().cache_location),
+        human(size)
+    );
+    Ok(())
+}
+
+/// the directory of a group, group:name or group:name:version in the cache, or the cache itself
+fn artifact_dir(root: &Path, artifact: Option<&str>) -> Result<PathBuf, Error> {
+    let Some(artifact) = artifact else {
+        return Ok(root.to_path_buf());
+    };
+    let parts: Vec<&str> = artifact.split(':').collect();
+    let invalid = |p: &&str| {
+        p.is_empty() || p.starts_with('.') || p.contains(['/', '\\']) || p.contains("..")
+    };
+    if parts.len() > 3 || parts.iter().any(invalid) {
+        return Err(anyhow!(
+            "{} is not a group, group:name or group:name:version",
+            artifact
+        ));
+    }
+    let mut dir = root.join(parts[0].replace('.', "/"));
+    for part in &parts[1..] {
+        dir = dir.join(part);
+    }
+    if !dir.starts_with(root) || dir == root {
+        return Err(anyhow!("{} is outside of the cache", artifact));
+    }
+    Ok(dir)
+}
+
+/// removes the group and name directories that are left empty, up to the root of the cache
+fn remove_empty_parents(dir: &Path) -> Result<(), Error> {
+    let root = Path::new(&config().cache_location);
+    let mut parent = dir.parent();
+    while let Some(dir) = parent {
+        if dir == root || !dir.starts_with(root) || fs::read_dir(dir)?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(dir)?;
+        parent = dir.parent();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    use crate::cache::{artifact_dir, human, lock_file, write_atomic, CacheLock};
+
+    #[test]
+    fn write_and_lock() {
+        let dir = std::env::temp_dir().join(format!("jargo-cache-{}", std::process::id()));
+        let file = dir.join("lib-1.0.jar");
+        let lock = CacheLock::acquire(&dir).unwrap();
+        write_atomic(&file, b"jar").unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"jar");
+        // only the lock file and the jar, no leftovers
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        // another handle of the lock file waits, like another process would
+        let started = Arc::new(Barrier::new(2));
+        let waiting = {
+            let (dir, started) = (dir.clone(), started.clone());
+            thread::spawn(move || {
+                started.wait();
+                let _lock = CacheLock::acquire(&dir).unwrap();
+                fs::read(dir.join("lib-1.0.jar")).unwrap()
+            })
+        };
+        started.wait();
+        write_atomic(&file, b"new jar").unwrap();
+        drop(lock);
+        assert_eq!(waiting.join().unwrap(), b"new jar");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn human_sizes() {
+        assert_eq!(human(512), "512 B");
+        assert_eq!(human(1536), "1.5 KiB");
+        assert_eq!(human(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn artifact_dirs() {
+        let root = Path::new("/cache");
+        assert_eq!(artifact_dir(root, None).unwrap(), root);
+        assert_eq!(
+            artifact_dir(root, Some("org.slf4j:slf4j-api:2.0.9")).unwrap(),
+            Path::new("/cache/org/slf4j/slf4j-api/2.0.9")
+        );
+        for artifact in [
+            ".", ".org", "org:.", "org:..", "org..x", "org:a/b", "org:a\\b", "org:a:", "a:b:c:d",
+        ] {
+            assert!(artifact_dir(root, Some(artifact)).is_err(), "{}", artifact);
+        }
+    }
+
+    #[test]
+    fn lock_files_outside_the_cache() {
+        let (cache, locks) = (Path::new("/jargo/repo"), Path::new("/jargo/locks"));
+        assert_eq!(
+            lock_file(cache, locks, &cache.join("org/slf4j/slf4j-api/2.0.9")),
+            Path::new("/jargo/locks/org/slf4j/slf4j-api/2.0.9.lock")
+        );
+        assert_eq!(
+            lock_file(cache, locks, Path::new("/jargo")),
+            Path::new("/jargo/.lock")
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Error};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// The checksums that maven repositories publish next to the artifacts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Sha512,
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl Algorithm {
+    /// the order in which they are tried, the first that the repository has is used
+    pub const STRONGEST_FIRST: [Algorithm; 4] = [
+        Algorithm::Sha512,
+        Algorithm::Sha256,
+        Algorithm::Sha1,
+        Algorithm::Md5,
+    ];
+
+    /// the extension of the checksum file, like jar.sha256
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Algorithm::Sha512 => "sha512",
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Md5 => "md5",
+        }
+    }
+
+    /// the hex encoded hash of the contents
+    pub fn hash(&self, contents: &[u8]) -> String {
+        match self {
+            Algorithm::Sha512 => hex::encode(Sha512::digest(contents)),
+            Algorithm::Sha256 => hex::encode(Sha256::digest(contents)),
+            Algorithm::Sha1 => hex::encode(Sha1::digest(contents)),
+            Algorithm::Md5 => hex::encode(Md5::digest(contents)),
+        }
+    }
+}
+
+/// What to do with a download that has no checksum in the repository, or a checksum that
+/// doesn't match. Set with `checksums` in [resolver], or per repository in [repositories].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ChecksumPolicy {
+    /// stop with an error, so that no unverified jar ends up on the classpath
+    #[default]
+    Fail,
+    /// show a warning and use the download anyway
+    Warn,
+    /// don't download the checksums at all
+    Ignore,
+}
+
+impl ChecksumPolicy {
+    pub(crate) fn parse(policy: &str) -> Result<Self, Error> {
+        match policy {
+            "fail" => Ok(ChecksumPolicy::Fail),
+            "warn" => Ok(ChecksumPolicy::Warn),
+            "ignore" => Ok(ChecksumPolicy::Ignore),
+            _ => Err(anyhow!(
+                "unknown checksum policy {}, use 'fail', 'warn' or 'ignore'",
+                policy
+            )),
+        }
+    }
+}
+
+/// The hash in a checksum file, lowercase. Like maven, it accepts a hash on its own,
+/// a hash followed by the filename (`sha1sum` style), and `SHA1 (file) = hash` (BSD style).
+pub fn parse(checksum_file: &[u8]) -> String {
+    let contents = String::from_utf8_lossy(checksum_file);
+    let line = contents
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default();
+    let hash = match line.rsplit_once("= ") {
+        Some((_, hash)) => hash,
+        None => line.split_whitespace().next().unwrap_or_default(),
+    };
+    hash.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checksum::{parse, Algorithm};
+
+    #[test]
+    fn parse_checksum_files() {
+        let hash = "2a6c3a1b2f4e0d9c8b7a6f5e4d3c2b1a09f8e7d6";
+        assert_eq!(parse(hash.as_bytes()), hash);
+        assert_eq!(
+            parse(format!("  {}\n", hash.to_uppercase()).as_bytes()),
+            hash
+        );
+        assert_eq!(
+            parse(format!("{}  guava-33.0.jar\n", hash).as_bytes()),
+            hash
+        );
+        assert_eq!(parse(format!("{} *guava-33.0.jar", hash).as_bytes()), hash);
+        assert_eq!(
+            parse(format!("\nSHA1 (guava-33.0.jar) = {}\n", hash).as_bytes()),
+            hash
+        );
+        assert_eq!(parse(b""), "");
+    }
+
+    #[test]
+    fn hashes() {
+        assert_eq!(
+            Algorithm::Sha1.hash(b""),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+        assert_eq!(Algorithm::Md5.hash(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            Algorithm::Sha256.hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(Algorithm::Sha512.hash(b"").len(), 128);
+    }
+}
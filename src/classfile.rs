@@ -0,0 +1,507 @@
+use std::borrow::Cow;
+
+use anyhow::{anyhow, Error};
+
+const MAGIC: u32 = 0xCAFEBABE;
+const ACC_PUBLIC: u16 = 0x0001;
+const ACC_PRIVATE: u16 = 0x0002;
+const ACC_STATIC: u16 = 0x0008;
+const ACC_INTERFACE: u16 = 0x0200;
+const ACC_ABSTRACT: u16 = 0x0400;
+
+/// A java class file, read as far as needed to inspect and rewrite the constant pool.
+///
+/// Everything after the constant pool (access flags, fields, methods, attributes) only refers to
+/// the constant pool by index, so it is kept as is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassFile {
+    pub minor_version: u16,
+    pub major_version: u16,
+    /// indexed like in the class file, so index 0 and the slots after a Long or Double are Unusable
+    pub constant_pool: Vec<Constant>,
+    body: Vec<u8>,
+}
+
+/// Constant pool entries.
+///
+/// Utf8 is kept in the 'modified UTF-8' encoding of the class file, because all names
+/// that need inspecting or rewriting are plain ascii.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Unusable,
+    Utf8(Vec<u8>),
+    Integer(u32),
+    Float(u32),
+    Long(u64),
+    Double(u64),
+    Class(u16),
+    String(u16),
+    FieldRef(u16, u16),
+    MethodRef(u16, u16),
+    InterfaceMethodRef(u16, u16),
+    NameAndType(u16, u16),
+    MethodHandle(u8, u16),
+    MethodType(u16),
+    Dynamic(u16, u16),
+    InvokeDynamic(u16, u16),
+    Module(u16),
+    Package(u16),
+}
+
+impl ClassFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.u32()? != MAGIC {
+            return Err(anyhow!("not a class file"));
+        }
+        let minor_version = reader.u16()?;
+        let major_version = reader.u16()?;
+        let count = reader.u16()? as usize;
+        let mut constant_pool = vec![Constant::Unusable];
+        while constant_pool.len() < count {
+            let tag = reader.u8()?;
+            let constant = match tag {
+                1 => {
+                    let length = reader.u16()? as usize;
+                    Constant::Utf8(reader.take(length)?.to_vec())
+                }
+                3 => Constant::Integer(reader.u32()?),
+                4 => Constant::Float(reader.u32()?),
+                5 => Constant::Long(reader.u64()?),
+                6 => Constant::Double(reader.u64()?),
+                7 => Constant::Class(reader.u16()?),
+                8 => Constant::String(reader.u16()?),
+                9 => Constant::FieldRef(reader.u16()?, reader.u16()?),
+                10 => Constant::MethodRef(reader.u16()?, reader.u16()?),
+                11 => Constant::InterfaceMethodRef(reader.u16()?, reader.u16()?),
+                12 => Constant::NameAndType(reader.u16()?, reader.u16()?),
+                15 => Constant::MethodHandle(reader.u8()?, reader.u16()?),
+                16 => Constant::MethodType(reader.u16()?),
+                17 => Constant::Dynamic(reader.u16()?, reader.u16()?),
+                18 => Constant::InvokeDynamic(reader.u16()?, reader.u16()?),
+                19 => Constant::Module(reader.u16()?),
+                20 => Constant::Package(reader.u16()?),
+                _ => {
+                    return Err(anyhow!(
+                        "unknown constant pool tag {} at {}",
+                        tag,
+                        reader.pos - 1
+                    ))
+                }
+            };
+            // longs and doubles take up two slots
+            let wide = matches!(constant, Constant::Long(_) | Constant::Double(_));
+            constant_pool.push(constant);
+            if wide {
+                constant_pool.push(Constant::Unusable);
+            }
+        }
+        if reader.bytes.len() < reader.pos + 6 {
+            return Err(anyhow!("unexpected end of class file"));
+        }
+        Ok(Self {
+            minor_version,
+            major_version,
+            constant_pool,
+            body: reader.bytes[reader.pos..].to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::with_capacity(self.body.len() * 2);
+        bytes.extend_from_slice(&MAGIC.to_be_bytes());
+        bytes.extend_from_slice(&self.minor_version.to_be_bytes());
+        bytes.extend_from_slice(&self.major_version.to_be_bytes());
+        bytes.extend_from_slice(&(self.constant_pool.len() as u16).to_be_bytes());
+        for constant in &self.constant_pool[1..] {
+            match constant {
+                Constant::Unusable => {}
+                Constant::Utf8(value) => {
+                    if value.len() > u16::MAX as usize {
+                        return Err(anyhow!("constant too long: {} bytes", value.len()));
+                    }
+                    bytes.push(1);
+                    bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+                    bytes.extend_from_slice(value);
+                }
+                Constant::Integer(value) => push_u32(&mut bytes, 3, *value),
+                Constant::Float(value) => push_u32(&mut bytes, 4, *value),
+                Constant::Long(value) => {
+                    bytes.push(5);
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                Constant::Double(value) => {
+                    bytes.push(6);
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                Constant::Class(index) => push_u16(&mut bytes, 7, *index),
+                Constant::String(index) => push_u16(&mut bytes, 8, *index),
+                Constant::FieldRef(a, b) => push_u16_u16(&mut bytes, 9, *a, *b),
+                Constant::MethodRef(a, b) => push_u16_u16(&mut bytes, 10, *a, *b),
+                Constant::InterfaceMethodRef(a, b) => push_u16_u16(&mut bytes, 11, *a, *b),
+                Constant::NameAndType(a, b) => push_u16_u16(&mut bytes, 12, *a, *b),
+                Constant::MethodHandle(kind, index) => {
+                    bytes.push(15);
+                    bytes.push(*kind);
+                    bytes.extend_from_slice(&index.to_be_bytes());
+                }
+                Constant::MethodType(index) => push_u16(&mut bytes, 16, *index),
+                Constant::Dynamic(a, b) => push_u16_u16(&mut bytes, 17, *a, *b),
+                Constant::InvokeDynamic(a, b) => push_u16_u16(&mut bytes, 18, *a, *b),
+                Constant::Module(index) => push_u16(&mut bytes, 19, *index),
+                Constant::Package(index) => push_u16(&mut bytes, 20, *index),
+            }
+        }
+        bytes.extend_from_slice(&self.body);
+        Ok(bytes)
+    }
+
+    /// the text of a Utf8 constant
+    pub fn utf8(&self, index: u16) -> Option<Cow<'_, str>> {
+        match self.constant_pool.get(index as usize) {
+            Some(Constant::Utf8(value)) => Some(String::from_utf8_lossy(value)),
+            _ => None,
+        }
+    }
+
+    pub fn access_flags(&self) -> u16 {
+        u16::from_be_bytes([self.body[0], self.body[1]])
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.access_flags() & (ACC_ABSTRACT | ACC_INTERFACE) != 0
+    }
+
+    /// the internal name (with slashes) of this class
+    pub fn this_class(&self) -> Option<Cow<'_, str>> {
+        // this_class follows the 2 bytes of the access flags
+        let index = u16::from_be_bytes([*self.body.get(2)?, *self.body.get(3)?]);
+        self.class_name(index)
+    }
+
+    /// the name of the java file the class was compiled from, from the SourceFile attribute
+    pub fn source_file(&self) -> Option<Cow<'_, str>> {
+        let mut reader = Reader {
+            bytes: &self.body,
+            pos: 6,
+        };
+        let interfaces = reader.u16().ok()? as usize;
+        reader.take(interfaces * 2).ok()?;
+        // fields and methods have the same layout
+        for _ in 0..2 {
+            let members = reader.u16().ok()?;
+            for _ in 0..members {
+                reader.take(6).ok()?;
+                skip_attributes(&mut reader)?;
+            }
+        }
+        let attributes = reader.u16().ok()?;
+        for _ in 0..attributes {
+            let name = reader.u16().ok()?;
+            let length = reader.u32().ok()? as usize;
+            let info = reader.take(length).ok()?;
+            if self.utf8(name).as_deref() == Some("SourceFile") && length == 2 {
+                return self.utf8(u16::from_be_bytes([info[0], info[1]]));
+            }
+        }
+        None
+    }
+
+    /// whether the class has a `public static void main(String[] args)`, to start a program with
+    pub fn has_main_method(&self) -> bool {
+        self.find_main_method().unwrap_or(false)
+    }
+
+    fn find_main_method(&self) -> Option<bool> {
+        let mut reader = Reader {
+            bytes: &self.body,
+            pos: 6,
+        };
+        let interfaces = reader.u16().ok()? as usize;
+        reader.take(interfaces * 2).ok()?;
+        let fields = reader.u16().ok()?;
+        for _ in 0..fields {
+            reader.take(6).ok()?;
+            skip_attributes(&mut reader)?;
+        }
+        let methods = reader.u16().ok()?;
+        for _ in 0..methods {
+            let access_flags = reader.u16().ok()?;
+            let name = reader.u16().ok()?;
+            let descriptor = reader.u16().ok()?;
+            skip_attributes(&mut reader)?;
+            if access_flags & (ACC_PUBLIC | ACC_STATIC) == ACC_PUBLIC | ACC_STATIC
+                && self.utf8(name).as_deref() == Some("main")
+                && self.utf8(descriptor).as_deref() == Some("([Ljava/lang/String;)V")
+            {
+                return Some(true);
+            }
+        }
+        Some(false)
+    }
+
+    /// What other classes compile against: the access flags, the superclass and interfaces,
+    /// and the fields and methods that aren't private, with the values of constant fields,
+    /// which javac copies into the classes that use them. One line per item
+    pub fn api(&self) -> Vec<String> {
+        self.find_api().unwrap_or_default()
+    }
+
+    fn find_api(&self) -> Option<Vec<String>> {
+        let mut reader = Reader {
+            bytes: &self.body,
+            pos: 0,
+        };
+        let access_flags = reader.u16().ok()?;
+        let this_class = reader.u16().ok()?;
+        let super_class = reader.u16().ok()?;
+        let mut api = vec![format!(
+            "class {:04x} {} {}",
+            access_flags,
+            self.class_name(this_class)?,
+            self.class_name(super_class).unwrap_or_default()
+        )];
+        let interfaces = reader.u16().ok()?;
+        for _ in 0..interfaces {
+            let interface = reader.u16().ok()?;
+            api.push(format!("implements {}", self.class_name(interface)?));
+        }
+        for kind in ["field", "method"] {
+            let members = reader.u16().ok()?;
+            for _ in 0..members {
+                let access_flags = reader.u16().ok()?;
+                let name = reader.u16().ok()?;
+                let descriptor = reader.u16().ok()?;
+                let mut member = format!(
+                    "{} {:04x} {} {}",
+                    kind,
+                    access_flags,
+                    self.utf8(name)?,
+                    self.utf8(descriptor)?
+                );
+                let attributes = reader.u16().ok()?;
+                for _ in 0..attributes {
+                    let name = reader.u16().ok()?;
+                    let length = reader.u32().ok()? as usize;
+                    let info = reader.take(length).ok()?;
+                    if self.utf8(name).as_deref() == Some("ConstantValue") && length == 2 {
+                        let value = u16::from_be_bytes([info[0], info[1]]);
+                        member.push_str(&format!(" = {}", self.constant_value(value)?));
+                    }
+                }
+                if access_flags & ACC_PRIVATE == 0 {
+                    api.push(member);
+                }
+            }
+        }
+        Some(api)
+    }
+
+    fn class_name(&self, index: u16) -> Option<Cow<'_, str>> {
+        match self.constant_pool.get(index as usize) {
+            Some(Constant::Class(name)) => self.utf8(*name),
+            _ => None,
+        }
+    }
+
+    fn constant_value(&self, index: u16) -> Option<String> {
+        match self.constant_pool.get(index as usize)? {
+            Constant::Integer(value) | Constant::Float(value) => Some(value.to_string()),
+            Constant::Long(value) | Constant::Double(value) => Some(value.to_string()),
+            Constant::String(value) => Some(format!("{:?}", self.utf8(*value)?)),
+            _ => None,
+        }
+    }
+
+    /// the internal names of all classes this class refers to in its constant pool.
+    /// Array classes are reported by their element type, primitive arrays are left out
+    pub fn class_references(&self) -> Vec<String> {
+        let mut classes = vec![];
+        for constant in &self.constant_pool {
+            if let Constant::Class(name) = constant {
+                if let Some(name) = self.utf8(*name) {
+                    let element = name.trim_start_matches('[');
+                    let class = if element.len() < name.len() {
+                        element.strip_prefix('L').and_then(|e| e.strip_suffix(';'))
+                    } else {
+                        Some(element)
+                    };
+                    if let Some(class) = class {
+                        if !classes.iter().any(|c| c == class) {
+                            classes.push(class.to_owned());
+                        }
+                    }
+                }
+            }
+        }
+        classes
+    }
+
+    /// whether the constant pool contains this exact Utf8 constant, eg. an annotation descriptor
+    pub fn has_utf8(&self, value: &str) -> bool {
+        self.constant_pool
+            .iter()
+            .any(|c| matches!(c, Constant::Utf8(v) if v == value.as_bytes()))
+    }
+
+    /// all Utf8 constants, mutable. These contain all names, descriptors, signatures
+    /// and string literals in the class
+    pub fn utf8_constants_mut(&mut self) -> impl Iterator<Item = &mut Vec<u8>> {
+        self.constant_pool.iter_mut().filter_map(|c| match c {
+            Constant::Utf8(value) => Some(value),
+            _ => None,
+        })
+    }
+}
+
+fn skip_attributes(reader: &mut Reader) -> Option<()> {
+    let attributes = reader.u16().ok()?;
+    for _ in 0..attributes {
+        reader.take(2).ok()?;
+        let length = reader.u32().ok()? as usize;
+        reader.take(length).ok()?;
+    }
+    Some(())
+}
+
+fn push_u16(bytes: &mut Vec<u8>, tag: u8, value: u16) {
+    bytes.push(tag);
+    bytes.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u16_u16(bytes: &mut Vec<u8>, tag: u8, a: u16, b: u16) {
+    push_u16(bytes, tag, a);
+    bytes.extend_from_slice(&b.to_be_bytes());
+}
+
+fn push_u32(bytes: &mut Vec<u8>, tag: u8, value: u32) {
+    bytes.push(tag);
+    bytes.extend_from_slice(&value.to_be_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.pos + n > self.bytes.len() {
+            return Err(anyhow!("unexpected end of class file"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::classfile::{ClassFile, Constant};
+
+    /// class com/example/B extends java/lang/Object, with a long constant, a String literal
+    /// and a method descriptor
+    pub(crate) fn sample_class() -> Vec<u8> {
+        let class = ClassFile {
+            minor_version: 0,
+            major_version: 61,
+            constant_pool: vec![
+                Constant::Unusable,
+                Constant::Utf8(b"com/example/B".to_vec()),
+                Constant::Class(1),
+                Constant::Utf8(b"java/lang/Object".to_vec()),
+                Constant::Class(3),
+                Constant::Long(42),
+                Constant::Unusable,
+                Constant::Utf8(b"[Lcom/example/C;".to_vec()),
+                Constant::Class(7),
+                Constant::Utf8(b"com.example.C".to_vec()),
+                Constant::String(9),
+                Constant::Utf8(b"SourceFile".to_vec()),
+                Constant::Utf8(b"B.java".to_vec()),
+                Constant::Utf8(b"(ILcom/example/C;J)V".to_vec()),
+            ],
+            // public, this_class, super_class, no interfaces, fields and methods,
+            // and a SourceFile attribute
+            body: vec![
+                0, 0x21, 0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 1, 0, 11, 0, 0, 0, 2, 0, 12,
+            ],
+        };
+        class.to_bytes().unwrap()
+    }
+
+    /// class com/example/K with a `public static final int LIMIT` of this value
+    /// and a private field
+    pub(crate) fn constant_class(value: u32) -> Vec<u8> {
+        let class = ClassFile {
+            minor_version: 0,
+            major_version: 61,
+            constant_pool: vec![
+                Constant::Unusable,
+                Constant::Utf8(b"com/example/K".to_vec()),
+                Constant::Class(1),
+                Constant::Utf8(b"java/lang/Object".to_vec()),
+                Constant::Class(3),
+                Constant::Utf8(b"LIMIT".to_vec()),
+                Constant::Utf8(b"I".to_vec()),
+                Constant::Utf8(b"ConstantValue".to_vec()),
+                Constant::Integer(value),
+                Constant::Utf8(b"count".to_vec()),
+            ],
+            // public, this_class, super_class, no interfaces, two fields, no methods
+            // and no attributes
+            body: vec![
+                0, 0x21, 0, 2, 0, 4, 0, 0, 0, 2, 0, 0x19, 0, 5, 0, 6, 0, 1, 0, 7, 0, 0, 0, 2, 0, 8,
+                0, 0x02, 0, 9, 0, 6, 0, 0, 0, 0, 0, 0,
+            ],
+        };
+        class.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let bytes = sample_class();
+        let class = ClassFile::parse(&bytes).unwrap();
+        assert_eq!(class.constant_pool.len(), 14);
+        assert_eq!(class.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn references() {
+        let class = ClassFile::parse(&sample_class()).unwrap();
+        assert_eq!(class.this_class().unwrap(), "com/example/B");
+        assert_eq!(
+            class.class_references(),
+            vec!["com/example/B", "java/lang/Object", "com/example/C"]
+        );
+        assert_eq!(class.source_file().unwrap(), "B.java");
+    }
+
+    #[test]
+    fn api() {
+        let class = ClassFile::parse(&constant_class(10)).unwrap();
+        assert_eq!(
+            class.api(),
+            vec![
+                "class 0021 com/example/K java/lang/Object",
+                "field 0019 LIMIT I = 10"
+            ]
+        );
+    }
+}
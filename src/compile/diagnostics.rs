@@ -0,0 +1,379 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use colored::Colorize;
+use regex::Regex;
+use serde_json::json;
+
+/// how the compiler messages are shown
+#[derive(Debug, Default, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum MessageFormat {
+    /// like cargo, with the source line and a caret under the column
+    #[default]
+    Human,
+    /// one json object per line, for editors and CI annotations
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// An error, warning or note from javac
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Diagnostic {
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    /// the first line is the summary, the others give details, like the symbol that wasn't found
+    pub message: String,
+    /// the source line
+    pub snippet: Option<String>,
+}
+
+/// The number of errors and warnings, like "3 errors, 12 warnings"
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Counts {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural =
+            |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+        match (self.errors, self.warnings) {
+            (0, warnings) => write!(f, "{}", plural(warnings, "warning")),
+            (errors, 0) => write!(f, "{}", plural(errors, "error")),
+            (errors, warnings) => write!(
+                f,
+                "{}, {}",
+                plural(errors, "error"),
+                plural(warnings, "warning")
+            ),
+        }
+    }
+}
+
+/// Shows the diagnostics of all compilations in a build.
+/// Warnings and notes that were shown for one source set aren't shown again for the next.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    format: MessageFormat,
+    shown: HashSet<Diagnostic>,
+    /// the number of warnings that were shown
+    warnings: usize,
+}
+
+impl Diagnostics {
+    pub fn new(format: MessageFormat) -> Self {
+        Self {
+            format,
+            shown: HashSet::new(),
+            warnings: 0,
+        }
+    }
+
+    /// shows what javac wrote to stderr, and counts the errors and warnings that were new
+    pub(crate) fn report(&mut self, javac_output: &str, project_root: &Path) -> Counts {
+        let mut counts = Counts::default();
+        for diagnostic in parse(javac_output) {
+            if diagnostic.severity != Severity::Error && !self.shown.insert(diagnostic.clone()) {
+                continue;
+            }
+            match diagnostic.severity {
+                Severity::Error => counts.errors += 1,
+                Severity::Warning => counts.warnings += 1,
+                Severity::Note => {}
+            }
+            self.show(&diagnostic, project_root);
+        }
+        self.warnings += counts.warnings;
+        counts
+    }
+
+    /// the number of warnings shown in the build so far
+    pub fn warnings(&self) -> usize {
+        self.warnings
+    }
+
+    /// shows one diagnostic, also for other files than java sources
+    pub(crate) fn show(&self, diagnostic: &Diagnostic, project_root: &Path) {
+        match self.format {
+            MessageFormat::Human => println!("{}", render(diagnostic, project_root)),
+            MessageFormat::Json => println!("{}", to_json(diagnostic, project_root)),
+        }
+    }
+
+    pub(crate) fn format(&self) -> MessageFormat {
+        self.format
+    }
+}
+
+/// Parses the diagnostics in the default javac format:
+///
+/// ```text
+/// /project/src/main/java/a/A.java:7: error: cannot find symbol
+///         String s = Foo.bar();
+///                    ^
+///   symbol:   variable Foo
+///   location: class A
+/// 1 error
+/// ```
+/// Diagnostics without a location, like `warning: [options] ...`, only have a message.
+pub(crate) fn parse(output: &str) -> Vec<Diagnostic> {
+    static LOCATED: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^(.+\.java):(\d+): (error|warning|note|Note): (.*)$").unwrap()
+    });
+    static UNLOCATED: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(error|warning|Note): (.*)$").unwrap());
+    static COUNTS: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\d+ (errors?|warnings?)$").unwrap());
+
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    for line in output.lines() {
+        if let Some(captures) = LOCATED.captures(line) {
+            diagnostics.push(Diagnostic {
+                file: Some(PathBuf::from(&captures[1])),
+                line: captures[2].parse().ok(),
+                column: None,
+                severity: severity(&captures[3]),
+                message: captures[4].to_owned(),
+                snippet: None,
+            });
+        } else if let Some(captures) = UNLOCATED.captures(line) {
+            diagnostics.push(Diagnostic {
+                file: None,
+                line: None,
+                column: None,
+                severity: severity(&captures[1]),
+                message: captures[2].to_owned(),
+                snippet: None,
+            });
+        } else if COUNTS.is_match(line) {
+            continue;
+        } else if let Some(diagnostic) = diagnostics.last_mut() {
+            // the source line comes right after the first line of the message, then the caret
+            if diagnostic.file.is_some() && diagnostic.snippet.is_none() {
+                diagnostic.snippet = Some(line.to_owned());
+            } else if diagnostic.snippet.is_some()
+                && diagnostic.column.is_none()
+                && line.trim() == "^"
+            {
+                diagnostic.column = line.find('^').map(|c| line[..c].chars().count() + 1);
+            } else {
+                diagnostic.message.push('\n');
+                diagnostic
+                    .message
+                    .push_str(line.strip_prefix("  ").unwrap_or(line));
+            }
+        }
+    }
+    diagnostics
+}
+
+fn severity(name: &str) -> Severity {
+    match name {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        _ => Severity::Note,
+    }
+}
+
+/// Like cargo does it:
+///
+/// ```text
+/// error: cannot find symbol
+///  --> src/main/java/a/A.java:7:20
+///   |
+/// 7 |         String s = Foo.bar();
+///   |                    ^
+///   = symbol:   variable Foo
+///   = location: class A
+/// ```
+fn render(diagnostic: &Diagnostic, project_root: &Path) -> String {
+    let label = match diagnostic.severity {
+        Severity::Error => "error".red().bold(),
+        Severity::Warning => "warning".yellow().bold(),
+        Severity::Note => "note".cyan().bold(),
+    };
+    let mut lines = diagnostic.message.lines();
+    let mut rendered = format!("{}: {}\n", label, lines.next().unwrap_or_default().bold());
+
+    let gutter = " ".repeat(diagnostic.line.map(|l| l.to_string().len()).unwrap_or(1));
+    let bar = "|".blue().bold();
+    if let Some(location) = location(diagnostic, project_root) {
+        rendered.push_str(&format!("{}{} {}\n", gutter, "-->".blue().bold(), location));
+    }
+    if let (Some(line), Some(snippet)) = (diagnostic.line, &diagnostic.snippet) {
+        rendered.push_str(&format!("{} {}\n", gutter, bar));
+        rendered.push_str(&format!(
+            "{} {} {}\n",
+            line.to_string().blue().bold(),
+            bar,
+            snippet
+        ));
+        if let Some(column) = diagnostic.column {
+            // tabs stay tabs, so that the caret lines up with the source line
+            let indent: String = snippet
+                .chars()
+                .take(column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            rendered.push_str(&format!("{} {} {}{}\n", gutter, bar, indent, "^".bold()));
+        }
+    }
+    for detail in lines {
+        rendered.push_str(&format!("{} {} {}\n", gutter, "=".blue().bold(), detail));
+    }
+    rendered
+}
+
+fn to_json(diagnostic: &Diagnostic, project_root: &Path) -> String {
+    json!({
+        "file": diagnostic.file.as_ref().map(|f| relative(f, project_root)),
+        "line": diagnostic.line,
+        "column": diagnostic.column,
+        "severity": diagnostic.severity.as_str(),
+        "message": diagnostic.message,
+        "snippet": diagnostic.snippet,
+    })
+    .to_string()
+}
+
+/// file:line:column, with the file relative to the project root
+fn location(diagnostic: &Diagnostic, project_root: &Path) -> Option<String> {
+    let mut location = relative(diagnostic.file.as_ref()?, project_root);
+    if let Some(line) = diagnostic.line {
+        location.push_str(&format!(":{}", line));
+        if let Some(column) = diagnostic.column {
+            location.push_str(&format!(":{}", column));
+        }
+    }
+    Some(location)
+}
+
+fn relative(file: &Path, project_root: &Path) -> String {
+    file.strip_prefix(project_root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use crate::compile::diagnostics::{parse, Counts, Diagnostic, Diagnostics, Severity};
+    use crate::compile::MessageFormat;
+
+    #[test]
+    fn parse_javac_output() {
+        let output = "warning: [options] source value 8 is obsolete and will be removed in a future release
+/p/src/main/java/A.java:6: warning: [unchecked] unchecked call to add(E) as a member of the raw type List
+\t\tl.add(\"x\");
+\t\t     ^
+  where E is a type-variable:
+    E extends Object declared in interface List
+/p/src/main/java/A.java:7: error: cannot find symbol
+        String s = Foo.bar();
+                   ^
+  symbol:   variable Foo
+  location: class A
+Note: Some input files use unchecked or unsafe operations.
+1 error
+2 warnings
+";
+        let diagnostics = parse(output);
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                file: None,
+                line: None,
+                column: None,
+                severity: Severity::Warning,
+                message:
+                    "[options] source value 8 is obsolete and will be removed in a future release"
+                        .into(),
+                snippet: None,
+            }
+        );
+        assert_eq!(diagnostics[1].column, Some(8));
+        assert_eq!(
+            diagnostics[1].message,
+            "[unchecked] unchecked call to add(E) as a member of the raw type List\nwhere E is a type-variable:\n  E extends Object declared in interface List"
+        );
+        assert_eq!(
+            diagnostics[2],
+            Diagnostic {
+                file: Some(PathBuf::from("/p/src/main/java/A.java")),
+                line: Some(7),
+                column: Some(20),
+                severity: Severity::Error,
+                message: "cannot find symbol\nsymbol:   variable Foo\nlocation: class A".into(),
+                snippet: Some("        String s = Foo.bar();".into()),
+            }
+        );
+        assert_eq!(diagnostics[3].severity, Severity::Note);
+
+        assert_eq!(
+            Counts {
+                errors: 3,
+                warnings: 12
+            }
+            .to_string(),
+            "3 errors, 12 warnings"
+        );
+        assert_eq!(
+            Counts {
+                errors: 0,
+                warnings: 1
+            }
+            .to_string(),
+            "1 warning"
+        );
+    }
+
+    /// the main and the test compilation of a project share one Diagnostics
+    #[test]
+    fn warnings_of_both_compilations_shown_once() {
+        let main = "warning: [options] bootstrap class path not set in conjunction with -source 8
+warning: [options] source value 8 is obsolete and will be removed in a future release
+/p/src/main/java/A.java:6: warning: [unchecked] unchecked call to add(E) as a member of the raw type List
+\t\tl.add(\"x\");
+\t\t     ^
+3 warnings
+";
+        let test = "warning: [options] bootstrap class path not set in conjunction with -source 8
+warning: [options] source value 8 is obsolete and will be removed in a future release
+/p/src/test/java/ATest.java:3: error: cannot find symbol
+        Foo.bar();
+        ^
+1 error
+2 warnings
+";
+        let mut diagnostics = Diagnostics::new(MessageFormat::Json);
+        let counts = diagnostics.report(main, Path::new("/p"));
+        assert_eq!((counts.errors, counts.warnings), (0, 3));
+        let counts = diagnostics.report(test, Path::new("/p"));
+        assert_eq!((counts.errors, counts.warnings), (1, 0));
+        assert_eq!(diagnostics.warnings(), 3);
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Error;
+use sha1::{Digest, Sha1};
+
+use crate::classfile::ClassFile;
+use crate::package::{digest, dir_entries};
+
+/// class files written this long before the compilation started still count as output of it,
+/// because file times can lag behind the clock a little
+const CLOCK_SLACK: Duration = Duration::from_secs(1);
+
+/// What was compiled the last time, to find out what needs compiling again.
+///
+/// Stored as text under target/jargo: the hashes of the javac options and of the classpath,
+/// then one line per source file with its hash, the hash of the api of its classes, the classes
+/// compiled from it and the classes of the same source set it refers to.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Fingerprints {
+    options: String,
+    classpath: String,
+    sources: BTreeMap<String, SourceFingerprint>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct SourceFingerprint {
+    hash: String,
+    /// see ClassFile::api
+    api: String,
+    classes: BTreeSet<String>,
+    references: BTreeSet<String>,
+}
+
+impl Fingerprints {
+    pub(super) fn new(options: &[OsString], classpath: &[String]) -> Result<Self, Error> {
+        let mut options_hasher = Sha1::new();
+        for option in options {
+            options_hasher.update(option.to_string_lossy().as_bytes());
+            options_hasher.update([0]);
+        }
+        Ok(Self {
+            options: hex::encode(options_hasher.finalize()),
+            classpath: hash_classpath(classpath)?,
+            sources: BTreeMap::new(),
+        })
+    }
+
+    /// the fingerprints of the previous compilation, None when there are none
+    pub(super) fn read(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        let mut lines = contents.lines();
+        let mut fingerprints = Self {
+            options: lines.next()?.strip_prefix("options\t")?.to_owned(),
+            classpath: lines.next()?.strip_prefix("classpath\t")?.to_owned(),
+            sources: BTreeMap::new(),
+        };
+        let names = |field: &str| -> BTreeSet<String> {
+            field
+                .split(' ')
+                .filter(|n| !n.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        for line in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 5 {
+                return None;
+            }
+            fingerprints.sources.insert(
+                fields[0].to_owned(),
+                SourceFingerprint {
+                    hash: fields[1].to_owned(),
+                    api: fields[2].to_owned(),
+                    classes: names(fields[3]),
+                    references: names(fields[4]),
+                },
+            );
+        }
+        Some(fingerprints)
+    }
+
+    pub(super) fn write(&self, path: &Path) -> Result<(), Error> {
+        let mut contents = format!("options\t{}\nclasspath\t{}\n", self.options, self.classpath);
+        for (source, fingerprint) in &self.sources {
+            let join =
+                |names: &BTreeSet<String>| names.iter().cloned().collect::<Vec<_>>().join(" ");
+            contents.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                source,
+                fingerprint.hash,
+                fingerprint.api,
+                join(&fingerprint.classes),
+                join(&fingerprint.references)
+            ));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// a change in the javac options or in the classpath can affect every class
+    pub(super) fn is_compatible(&self, other: &Fingerprints) -> bool {
+        self.options == other.options && self.classpath == other.classpath
+    }
+
+    /// true when no source was added, changed or removed since the previous compilation
+    pub(super) fn is_unchanged(&self, hashes: &BTreeMap<String, String>) -> bool {
+        self.sources.len() == hashes.len()
+            && hashes
+                .iter()
+                .all(|(source, hash)| self.sources.get(source).map(|f| &f.hash) == Some(hash))
+    }
+
+    /// takes over what is known about the sources from the previous compilation
+    pub(super) fn continue_from(&mut self, previous: Fingerprints) {
+        self.sources = previous.sources;
+    }
+
+    /// Compares the source hashes with the previous compilation.
+    /// Returns the sources that have changed, together with the sources that refer to classes
+    /// of changed or removed sources, directly or through other outdated sources, because a class
+    /// can use members it inherits from another. Deletes the class files of the changed and
+    /// removed sources, so that no classes of deleted code remain.
+    pub(super) fn outdated(
+        &self,
+        hashes: &BTreeMap<String, String>,
+        classes_dir: &Path,
+    ) -> Result<Vec<String>, Error> {
+        let changed: BTreeSet<&String> = hashes
+            .iter()
+            .filter(|(source, hash)| self.sources.get(*source).map(|f| &f.hash) != Some(hash))
+            .map(|(source, _)| source)
+            .collect();
+        let removed = self.sources.keys().filter(|s| !hashes.contains_key(*s));
+
+        let mut stale_classes = HashSet::new();
+        for source in changed.iter().copied().chain(removed) {
+            if let Some(fingerprint) = self.sources.get(source) {
+                for class in &fingerprint.classes {
+                    let class_file = classes_dir.join(format!("{}.class", class));
+                    if class_file.exists() {
+                        fs::remove_file(class_file)?;
+                    }
+                    stale_classes.insert(class);
+                }
+            }
+        }
+
+        let mut outdated: BTreeSet<String> = changed.into_iter().cloned().collect();
+        loop {
+            let dependents: Vec<(&String, &SourceFingerprint)> = self
+                .sources
+                .iter()
+                .filter(|(source, fingerprint)| {
+                    hashes.contains_key(*source)
+                        && !outdated.contains(*source)
+                        && fingerprint
+                            .references
+                            .iter()
+                            .any(|c| stale_classes.contains(c))
+                })
+                .collect();
+            if dependents.is_empty() {
+                return Ok(outdated.into_iter().collect());
+            }
+            for (source, fingerprint) in dependents {
+                outdated.insert(source.clone());
+                stale_classes.extend(&fingerprint.classes);
+            }
+        }
+    }
+
+    /// Takes over the source hashes and finds out which classes the compiled sources produced,
+    /// what they refer to and their api, from the class files that were written since
+    /// the compilation started.
+    /// Returns true when the api of a source that was compiled before has changed.
+    pub(super) fn update(
+        &mut self,
+        hashes: BTreeMap<String, String>,
+        compiled: &[String],
+        classes_dir: &Path,
+        started: SystemTime,
+    ) -> Result<bool, Error> {
+        let mut previous_apis = BTreeMap::new();
+        let mut sources = BTreeMap::new();
+        for (source, hash) in hashes {
+            let mut fingerprint = self.sources.remove(&source).unwrap_or_default();
+            if compiled.contains(&source) {
+                if !fingerprint.api.is_empty() {
+                    previous_apis.insert(source.clone(), fingerprint.api.clone());
+                }
+                fingerprint.api.clear();
+                fingerprint.classes.clear();
+                fingerprint.references.clear();
+            }
+            fingerprint.hash = hash;
+            sources.insert(source, fingerprint);
+        }
+        self.sources = sources;
+
+        let since = started - CLOCK_SLACK;
+        let mut apis: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, file) in dir_entries(classes_dir)? {
+            let Some(class_name) = name.strip_suffix(".class") else {
+                continue;
+            };
+            if fs::metadata(&file)?.modified()? < since {
+                continue;
+            }
+            let class = ClassFile::parse(&fs::read(&file)?)?;
+            let Some(source) = self.source_of(class_name, &class) else {
+                continue;
+            };
+            if let Some(fingerprint) = self.sources.get_mut(&source) {
+                fingerprint.classes.insert(class_name.to_owned());
+                fingerprint.references.extend(class.class_references());
+                apis.entry(source).or_default().extend(class.api());
+            }
+        }
+        for (source, mut api) in apis {
+            // the order of the class files in the directory doesn't matter
+            api.sort();
+            let mut hasher = Sha1::new();
+            for line in api {
+                hasher.update(line.as_bytes());
+                hasher.update([b'\n']);
+            }
+            if let Some(fingerprint) = self.sources.get_mut(&source) {
+                fingerprint.api = hex::encode(hasher.finalize());
+            }
+        }
+
+        // only the references to classes of this source set matter
+        let classes: HashSet<String> = self
+            .sources
+            .values()
+            .flat_map(|f| f.classes.iter().cloned())
+            .collect();
+        for fingerprint in self.sources.values_mut() {
+            let own = fingerprint.classes.clone();
+            fingerprint
+                .references
+                .retain(|c| classes.contains(c) && !own.contains(c));
+        }
+        Ok(previous_apis
+            .iter()
+            .any(|(source, api)| self.sources.get(source).map(|f| &f.api) != Some(api)))
+    }
+
+    /// The source file of a class is found from its package and the SourceFile attribute,
+    /// or else from the name of the (outer) class
+    fn source_of(&self, class_name: &str, class: &ClassFile) -> Option<String> {
+        let package = class_name.rsplit_once('/').map(|(package, _)| package);
+        let in_package = |file: &str| match package {
+            Some(package) => format!("{}/{}", package, file),
+            None => file.to_owned(),
+        };
+        if let Some(source_file) = class.source_file() {
+            let source = in_package(&source_file);
+            if self.sources.contains_key(&source) {
+                return Some(source);
+            }
+        }
+        let outer_class = class_name.split('$').next().unwrap_or(class_name);
+        let source = format!("{}.java", outer_class);
+        if self.sources.contains_key(&source) {
+            Some(source)
+        } else {
+            None
+        }
+    }
+}
+
+/// the hashes of the source files, by their path relative to the source root
+pub(super) fn hash_sources(
+    source_root: &Path,
+    source_files: &[PathBuf],
+) -> Result<BTreeMap<String, String>, Error> {
+    let mut hashes = BTreeMap::new();
+    for file in source_files {
+        let name = file
+            .strip_prefix(source_root)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        hashes.insert(name, hex::encode(digest(&fs::read(file)?)));
+    }
+    Ok(hashes)
+}
+
+/// Jars are fingerprinted by their size and modification time, which is enough to notice
+/// an updated snapshot. Directories, like target/classes for the tests, by the files in them.
+fn hash_classpath(classpath: &[String]) -> Result<String, Error> {
+    let mut hasher = Sha1::new();
+    let mut add = |name: &str, path: &Path| -> Result<(), Error> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        hasher.update(format!(
+            "{} {} {}\n",
+            name,
+            metadata.len(),
+            modified.as_nanos()
+        ));
+        Ok(())
+    };
+    for entry in classpath {
+        let path = Path::new(entry);
+        if path.is_dir() {
+            add(entry, path)?;
+            for (name, file) in dir_entries(path)? {
+                add(&name, &file)?;
+            }
+        } else if path.exists() {
+            add(entry, path)?;
+        }
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// removes the class files, but not the resources, from the classes directory
+pub(super) fn delete_classes(classes_dir: &Path) -> Result<(), Error> {
+    for (name, file) in dir_entries(classes_dir)? {
+        if name.ends_with(".class") {
+            fs::remove_file(file)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fs;
+    use std::time::SystemTime;
+
+    use crate::classfile::test::constant_class;
+    use crate::compile::incremental::{Fingerprints, SourceFingerprint};
+
+    fn source(hash: &str, classes: &[&str], references: &[&str]) -> SourceFingerprint {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        SourceFingerprint {
+            hash: hash.into(),
+            api: format!("api of {}", hash),
+            classes: names(classes),
+            references: names(references),
+        }
+    }
+
+    #[test]
+    fn outdated_sources() {
+        let dir = std::env::temp_dir().join(format!("jargo-incremental-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a")).unwrap();
+        for class in ["a/A", "a/A$1", "a/B", "a/C", "a/D", "a/F"] {
+            fs::write(dir.join(format!("{}.class", class)), []).unwrap();
+        }
+        let previous = Fingerprints {
+            sources: BTreeMap::from([
+                ("a/A.java".into(), source("1", &["a/A", "a/A$1"], &[])),
+                ("a/B.java".into(), source("2", &["a/B"], &["a/A$1"])),
+                ("a/C.java".into(), source("3", &["a/C"], &["a/D"])),
+                ("a/D.java".into(), source("4", &["a/D"], &[])),
+                ("a/F.java".into(), source("7", &["a/F"], &["a/B"])),
+            ]),
+            ..Default::default()
+        };
+        // A changed, D is removed, E is new, and F uses B, which uses A
+        let hashes = BTreeMap::from([
+            ("a/A.java".into(), "5".into()),
+            ("a/B.java".into(), "2".into()),
+            ("a/C.java".into(), "3".into()),
+            ("a/E.java".into(), "6".into()),
+            ("a/F.java".into(), "7".into()),
+        ]);
+
+        let outdated = previous.outdated(&hashes, &dir).unwrap();
+        assert_eq!(
+            outdated,
+            vec!["a/A.java", "a/B.java", "a/C.java", "a/E.java", "a/F.java"]
+        );
+
+        let remaining: BTreeSet<String> = fs::read_dir(dir.join("a"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            remaining,
+            BTreeSet::from([
+                "B.class".to_owned(),
+                "C.class".to_owned(),
+                "F.class".to_owned()
+            ])
+        );
+
+        previous.write(&dir.join("fingerprints")).unwrap();
+        assert_eq!(
+            Fingerprints::read(&dir.join("fingerprints")).unwrap(),
+            previous
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn changed_constant_changes_the_api() {
+        let dir = std::env::temp_dir().join(format!("jargo-api-{}", std::process::id()));
+        fs::create_dir_all(dir.join("com/example")).unwrap();
+        let hashes = BTreeMap::from([
+            ("com/example/K.java".to_owned(), "1".to_owned()),
+            ("com/example/U.java".to_owned(), "2".to_owned()),
+        ]);
+        let compiled = vec!["com/example/K.java".to_owned()];
+        let mut fingerprints = Fingerprints::default();
+        let mut compile = |value: u32| {
+            let started = SystemTime::now();
+            fs::write(dir.join("com/example/K.class"), constant_class(value)).unwrap();
+            fingerprints
+                .update(hashes.clone(), &compiled, &dir, started)
+                .unwrap()
+        };
+
+        // the first compilation has nothing to compare with
+        assert!(!compile(10));
+        assert!(!compile(10));
+        assert!(compile(11));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
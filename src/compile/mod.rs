@@ -1,30 +1,42 @@
+use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::time::SystemTime;
 
-use crate::compile::PathNode::*;
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use colored::Colorize;
 
-use crate::project::Project;
+use crate::deploader::Classpath;
+use crate::error::JargoError;
+use crate::package::dir_entries;
+use crate::project::{CompileOptions, Project};
 
-const SOURCES: &str = "src/main/java";
-const TESTSOURCES: &str = "src/test/java";
-const RESOURCES: &str = "src/main/resources";
-const TESTRESOURCES: &str = "src/main/java";
+pub(crate) mod diagnostics;
+mod incremental;
+mod resources;
 
-const TARGET_MAIN: &str = "target/classes";
-const TARGET_TEST: &str = "target/test-classes";
+pub use diagnostics::{Diagnostics, MessageFormat};
+use incremental::Fingerprints;
 
-/// internal view of the src filesystem
-#[derive(Debug)]
-enum PathNode {
-    DirNode(PathBuf, Vec<PathNode>, Vec<PathNode>),
-    FileNode(PathBuf),
-}
+pub const SOURCES: &str = "src/main/java";
+pub const TESTSOURCES: &str = "src/test/java";
+pub const RESOURCES: &str = "src/main/resources";
+pub const TESTRESOURCES: &str = "src/test/resources";
+
+/// relative to the target directory of the project
+pub const TARGET_MAIN: &str = "classes";
+pub const TARGET_TEST: &str = "test-classes";
 
-/// runs the compile stage
-pub fn run(project: &Project) -> Result<(), Error> {
+/// where annotation processors write the sources they generate, relative to the target directory
+pub const GENERATED_SOURCES: &str = "generated-sources/annotations";
+pub const GENERATED_TEST_SOURCES: &str = "generated-test-sources/test-annotations";
+
+/// runs the compile stage, and copies the resources next to the classes
+pub fn run(
+    project: &Project,
+    classpath: &Classpath,
+    diagnostics: &mut Diagnostics,
+) -> Result<(), Error> {
     println!(
         "{} {}.{}-{}",
         "Compiling".green(),
@@ -33,82 +45,258 @@ pub fn run(project: &Project) -> Result<(), Error> {
         project.version
     );
 
-    let root = PathBuf::from(&project.project_root).join(SOURCES);
+    compile_source_set(
+        project,
+        SourceSet {
+            sources: SOURCES,
+            generated: GENERATED_SOURCES,
+            target: TARGET_MAIN,
+        },
+        classpath.runtime(),
+        &classpath.processors,
+        &project.compile,
+        diagnostics,
+    )?;
+    resources::copy(project, RESOURCES, TARGET_MAIN)
+}
 
-    let mut src_tree = DirNode(root.clone(), Vec::new(), Vec::new());
-    determine_src_tree(root, &mut src_tree)?;
-    compile_source_dir(project, &mut src_tree)?;
+/// compiles the tests against the main classes and the test dependencies, and copies the test resources
+pub fn run_tests(
+    project: &Project,
+    classpath: &Classpath,
+    diagnostics: &mut Diagnostics,
+) -> Result<(), Error> {
+    let main_classes = PathBuf::from(&project.target_dir).join(TARGET_MAIN);
+    let mut test_classpath = vec![main_classes.to_string_lossy().to_string()];
+    test_classpath.extend(classpath.test_runtime());
+    compile_source_set(
+        project,
+        SourceSet {
+            sources: TESTSOURCES,
+            generated: GENERATED_TEST_SOURCES,
+            target: TARGET_TEST,
+        },
+        &test_classpath,
+        &classpath.processors,
+        &project.test_compile,
+        diagnostics,
+    )?;
+    resources::copy(project, TESTRESOURCES, TARGET_TEST)
+}
 
-    Ok(())
+/// the directories of a source set, the sources relative to the project root
+/// and the others relative to the target directory
+struct SourceSet {
+    sources: &'static str,
+    generated: &'static str,
+    target: &'static str,
 }
 
-/// walks the source tree and compiles any java files
-fn compile_source_dir(project: &Project, src_tree: &mut PathNode) -> Result<(), Error> {
-    if let DirNode(dir_name, subdirs, contents) = src_tree {
-        if !contents.is_empty() {
-            let mut javac = if cfg!(target_os = "windows") {
-                vec!["/C"]
-            } else {
-                vec![]
-            };
-            let classes = PathBuf::from(&project.project_root).join(TARGET_MAIN);
-            let classes = classes.to_str().unwrap();
-
-            javac.append(&mut vec!["javac", "-d", classes, "-sourcepath"]);
-            javac.push(dir_name.to_str().unwrap().into());
-            for source in contents {
-                if let FileNode(source_name) = source {
-                    let name = source_name.to_str().unwrap();
-                    javac.push(name);
-                }
-            }
-
-            let output = if cfg!(target_os = "windows") {
-                Command::new("cmd")
-                    .args(javac)
-                    .output()
-                    .expect("failed to execute process")
-            } else {
-                Command::new("sh")
-                    .arg("-c")
-                    .arg(javac.join(" "))
-                    .output()
-                    .expect("failed to execute process")
-            };
-            if !output.stderr.is_empty() {
-                println!("{}", String::from_utf8(output.stderr)?.red());
-            }
-            println!("{}", String::from_utf8(output.stdout)?);
+/// Compiles the java files in a source set (like src/main/java) with a single javac invocation,
+/// so that classes in different packages can see each other.
+///
+/// Only the sources that changed since the last time are compiled, together with the sources
+/// that depend on them. Everything is compiled when the options or the classpath have changed,
+/// or when the api of a changed source has.
+///
+/// Generated sources are a source root too. javac doesn't tell which sources a processor
+/// generated code for, so when the last compilation generated any, a change to the sources
+/// compiles the whole source set again.
+fn compile_source_set(
+    project: &Project,
+    source_set: SourceSet,
+    classpath: &[String],
+    processors: &[String],
+    compile_options: &CompileOptions,
+    diagnostics: &mut Diagnostics,
+) -> Result<(), Error> {
+    let SourceSet {
+        sources,
+        generated,
+        target,
+    } = source_set;
+    let root = PathBuf::from(&project.project_root).join(sources);
+    if !root.exists() {
+        return Ok(());
+    }
+    let generated = PathBuf::from(&project.target_dir).join(generated);
+    fs::create_dir_all(&generated)?;
+
+    let mut source_files = vec![];
+    find_source_files(&root, &mut source_files)?;
+    source_files.sort();
+
+    let classes = PathBuf::from(&project.target_dir).join(target);
+    fs::create_dir_all(&classes)?;
+
+    let mut options: Vec<OsString> = vec![
+        "-d".into(),
+        classes.clone().into(),
+        "-s".into(),
+        generated.clone().into(),
+        "-sourcepath".into(),
+        std::env::join_paths([&root, &generated])?,
+    ];
+    // without a -processorpath javac looks for processors on the classpath, like lombok
+    // when it is a dependency
+    if !processors.is_empty() {
+        options.push("-processorpath".into());
+        options.push(std::env::join_paths(processors)?);
+    }
+    options.extend(compile_options.javac_args().into_iter().map(OsString::from));
+    let fingerprints_file = PathBuf::from(&project.target_dir)
+        .join("jargo")
+        .join(format!("{}.fingerprints", file_name(&classes)));
+    // another JDK can compile differently, so it counts as an option
+    let mut fingerprinted = vec![project.javac().get_program().to_owned()];
+    fingerprinted.extend(options.iter().cloned());
+    // a new version of a processor can generate other code
+    let mut fingerprinted_classpath = classpath.to_vec();
+    fingerprinted_classpath.extend(processors.iter().cloned());
+    let mut fingerprints = Fingerprints::new(&fingerprinted, &fingerprinted_classpath)?;
+    let hashes = incremental::hash_sources(&root, &source_files)?;
+
+    let has_generated = !dir_entries(&generated)?.is_empty();
+    let previous = Fingerprints::read(&fingerprints_file)
+        .filter(|p| p.is_compatible(&fingerprints))
+        .filter(|p| !has_generated || p.is_unchanged(&hashes));
+    let outdated = match &previous {
+        Some(previous) => previous.outdated(&hashes, &classes)?,
+        None => {
+            incremental::delete_classes(&classes)?;
+            fs::remove_dir_all(&generated)?;
+            fs::create_dir_all(&generated)?;
+            hashes.keys().cloned().collect()
         }
-        for subdir in subdirs {
-            compile_source_dir(project, subdir)?;
+    };
+    if let Some(previous) = previous {
+        fingerprints.continue_from(previous);
+    }
+
+    // the classes that are not compiled again are found in the classes directory
+    let mut classpath = classpath.to_vec();
+    classpath.push(classes.to_string_lossy().to_string());
+    let mut compile = |outdated: &[String], fingerprints: &mut Fingerprints| {
+        let started = SystemTime::now();
+        if !outdated.is_empty() {
+            let files: Vec<PathBuf> = outdated.iter().map(|source| root.join(source)).collect();
+            javac(
+                project,
+                &options,
+                &classpath,
+                &files,
+                &classes,
+                sources,
+                diagnostics,
+            )?;
         }
+        fingerprints.update(hashes.clone(), outdated, &classes, started)
+    };
+    let api_changed = compile(&outdated, &mut fingerprints)?;
+    if api_changed && outdated.len() < hashes.len() {
+        // the other classes can use the changed api without referring to the class,
+        // like constants that javac copied into them, so everything is compiled again
+        incremental::delete_classes(&classes)?;
+        let all: Vec<String> = hashes.keys().cloned().collect();
+        compile(&all, &mut fingerprints)?;
     }
+    fingerprints.write(&fingerprints_file)
+}
+
+fn javac(
+    project: &Project,
+    options: &[OsString],
+    classpath: &[String],
+    source_files: &[PathBuf],
+    classes: &Path,
+    sources: &str,
+    diagnostics: &mut Diagnostics,
+) -> Result<(), Error> {
+    // the source files are passed in an argument file, because the list can be longer than
+    // the command line allows
+    let argfile = classes.with_extension("javac-sources");
+    write_argfile(&argfile, source_files)?;
+
+    let mut javac = project.javac();
+    // the diagnostics are parsed, so they must not be translated
+    javac.arg("-J-Duser.language=en");
+    javac.args(options);
+    if !classpath.is_empty() {
+        javac
+            .arg("-classpath")
+            .arg(std::env::join_paths(classpath)?);
+    }
+    javac.arg(format!("@{}", argfile.to_string_lossy()));
+
+    let output = javac
+        .output()
+        .map_err(|e| anyhow!("failed to execute javac: {}", e))?;
+    if !output.stdout.is_empty() {
+        println!("{}", String::from_utf8(output.stdout)?);
+    }
+    let counts = diagnostics.report(
+        &String::from_utf8_lossy(&output.stderr),
+        Path::new(&project.project_root),
+    );
+    if !output.status.success() {
+        let message = if counts.errors > 0 {
+            counts.to_string()
+        } else {
+            format!("javac exiting with {}", output.status)
+        };
+        return Err(JargoError::Compile {
+            project: project.name.clone(),
+            sources: sources.into(),
+            message,
+        }
+        .into());
+    }
+    if counts.warnings > 0 && diagnostics.format() == MessageFormat::Human {
+        println!(
+            "{}: {} generated {}",
+            "warning".yellow().bold(),
+            sources,
+            counts
+        );
+    }
+    Ok(())
+}
+
+/// one quoted argument per line, quotes and backslashes escaped, as javac and java expect
+pub(crate) fn write_argfile<S: AsRef<OsStr>>(argfile: &Path, args: &[S]) -> Result<(), Error> {
+    let contents: Vec<String> = args
+        .iter()
+        .map(|arg| {
+            let arg = arg
+                .as_ref()
+                .to_string_lossy()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            format!("\"{}\"", arg)
+        })
+        .collect();
+    fs::write(argfile, contents.join("\n"))?;
     Ok(())
 }
 
-/// the source tree on disk is first read into memory
-fn determine_src_tree(parent: PathBuf, parent_node: &mut PathNode) -> Result<(), Error> {
-    let paths = fs::read_dir(&parent)?;
+/// finds all java files under the source root
+fn find_source_files(parent: &Path, source_files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let paths = fs::read_dir(parent)?;
 
     for path in paths {
         let path = path?;
 
         if path.metadata()?.is_dir() {
-            let mut subdir = DirNode(path.path(), Vec::new(), Vec::new());
-            determine_src_tree(path.path(), &mut subdir)?;
-            if let DirNode(_, subdirs, _) = parent_node {
-                subdirs.push(subdir);
-            }
-        } else {
-            let name = path.file_name();
-            let name = name.to_str().unwrap().to_owned();
-            if name.ends_with(".java") {
-                if let DirNode(_, _, contents) = parent_node {
-                    contents.push(FileNode(path.path()));
-                }
-            }
+            find_source_files(&path.path(), source_files)?;
+        } else if path.file_name().to_string_lossy().ends_with(".java") {
+            source_files.push(path.path());
         }
     }
     Ok(())
 }
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Error};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::package::dir_entries;
+use crate::project::Project;
+
+/// Copies the resources of a source set (like src/main/resources) to its classes directory.
+///
+/// Files that match the filter globs in [resources] get their ${...} placeholders replaced.
+/// Other files are skipped when the copy is up to date.
+pub(crate) fn copy(project: &Project, resources: &str, target: &str) -> Result<(), Error> {
+    let root = PathBuf::from(&project.project_root);
+    let source_dir = root.join(resources);
+    if !source_dir.exists() {
+        return Ok(());
+    }
+    let target_dir = PathBuf::from(&project.target_dir).join(target);
+    let filter = glob_set(&project.resources.filter)?;
+
+    for (name, file) in dir_entries(&source_dir)? {
+        let destination = target_dir.join(&name);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if filter.is_match(&name) {
+            copy_filtered(&file, &destination, &project.resources.properties)?;
+        } else if !is_up_to_date(&file, &destination)? {
+            fs::copy(&file, &destination)?;
+        }
+    }
+    Ok(())
+}
+
+/// the copy is written again when the properties change, so the contents are compared instead
+/// of the modification times
+fn copy_filtered(
+    file: &Path,
+    destination: &Path,
+    properties: &BTreeMap<String, String>,
+) -> Result<(), Error> {
+    let contents = fs::read(file)?;
+    let filtered = match String::from_utf8(contents) {
+        Ok(text) => replace_placeholders(&text, properties).into_bytes(),
+        // not text, so nothing to replace
+        Err(e) => e.into_bytes(),
+    };
+    if fs::read(destination).ok().as_ref() != Some(&filtered) {
+        fs::write(destination, filtered)?;
+    }
+    Ok(())
+}
+
+fn is_up_to_date(file: &Path, destination: &Path) -> Result<bool, Error> {
+    let destination = match fs::metadata(destination) {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(false),
+    };
+    let source = fs::metadata(file)?;
+    Ok(source.len() == destination.len() && destination.modified()? >= source.modified()?)
+}
+
+/// Replaces ${key} with the value of the property.
+/// Unknown keys are left alone, because they may be meant for the application, like ${server.port}
+/// in a Spring application.yml
+fn replace_placeholders(text: &str, properties: &BTreeMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        match placeholder
+            .find('}')
+            .and_then(|end| properties.get(&placeholder[2..end]).map(|v| (end, v)))
+        {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                result.push_str("${");
+                rest = &placeholder[2..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// globs on the path relative to the resources directory, `*` doesn't match a '/' but `**` does
+fn glob_set(globs: &[String]) -> Result<GlobSet, Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let glob = GlobBuilder::new(glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| anyhow!("invalid glob in resources.filter: {}", e))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use crate::compile::resources::{glob_set, replace_placeholders};
+
+    #[test]
+    fn placeholders() {
+        let properties = BTreeMap::from([
+            ("project.version".to_owned(), "1.0".to_owned()),
+            ("db.url".to_owned(), "jdbc:h2:mem:".to_owned()),
+        ]);
+        assert_eq!(
+            replace_placeholders(
+                "version: ${project.version}\nurl: ${db.url}test\nport: ${server.port}\n$ {x} ${",
+                &properties
+            ),
+            "version: 1.0\nurl: jdbc:h2:mem:test\nport: ${server.port}\n$ {x} ${"
+        );
+    }
+
+    #[test]
+    fn filter_globs() {
+        let filter = glob_set(&["application*.yml".into(), "**/*.properties".into()]).unwrap();
+        assert!(filter.is_match("application-dev.yml"));
+        assert!(filter.is_match("config/app.properties"));
+        assert!(filter.is_match("app.properties"));
+        assert!(!filter.is_match("static/logo.png"));
+        assert!(!filter.is_match("config/application.yml"));
+    }
+}
@@ -1,23 +1,109 @@
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use toml::{Table, Value};
+
+use crate::error::JargoError;
+use crate::signature::{SignatureConfig, SignaturePolicy};
+
 /// Contains any config elements
 pub struct Config {
     pub cache_location: String,
     pub user_home: String,
+    /// [signatures] in ~/jargo/config.toml, None when signatures are not checked
+    pub signatures: Option<SignatureConfig>,
 }
 
 pub static CONFIG: OnceLock<Config> = OnceLock::new();
 
 /// default config
 pub fn config() -> &'static Config {
-    CONFIG.get_or_init(|| {
-        let user_home = home::home_dir().map(|p|p.to_str().unwrap().to_owned())
-            .expect("Can not find $HOME in environment");
-        Config {
-            cache_location: format!("{}/jargo/repo", user_home).into(), //TODO make it '.jargo'
-            user_home,
-        }
-    });
+    CONFIG.get_or_init(default_config);
     CONFIG.get().unwrap()
 }
+
+fn default_config() -> Config {
+    let user_home = home::home_dir().map(|p|p.to_str().unwrap().to_owned())
+        .expect("Can not find $HOME in environment");
+    Config {
+        cache_location: format!("{}/jargo/repo", user_home), //TODO make it '.jargo'
+        user_home,
+        signatures: None,
+    }
+}
+
+/// Reads the global config in ~/jargo/config.toml, when there is one.
+/// Must be called before config() is used, otherwise that has the defaults.
+pub fn init() -> Result<(), JargoError> {
+    let mut config = default_config();
+    let jargo_home = Path::new(&config.user_home).join("jargo");
+    let file = jargo_home.join("config.toml");
+    if file.exists() {
+        let error = |key: Option<&str>, message: String| JargoError::Manifest {
+            file: file.clone(),
+            key: key.map(String::from),
+            position: None,
+            message,
+        };
+        let table = fs::read_to_string(&file)
+            .map_err(|e| error(None, e.to_string()))?
+            .parse::<Table>()
+            .map_err(|e| error(None, e.message().to_owned()))?;
+        if let Some(signatures) = table.get("signatures") {
+            let string = |key: &str| -> Result<Option<&str>, JargoError> {
+                match signatures.get(key) {
+                    Some(Value::String(value)) => Ok(Some(value)),
+                    Some(_) => Err(error(
+                        Some(&format!("signatures.{}", key)),
+                        "should be a string".into(),
+                    )),
+                    None => Ok(None),
+                }
+            };
+            let policy = |key: &str| -> Result<SignaturePolicy, JargoError> {
+                match string(key)? {
+                    Some(policy) => SignaturePolicy::parse(policy)
+                        .map_err(|e| error(Some(&format!("signatures.{}", key)), e.to_string())),
+                    None => Ok(SignaturePolicy::default()),
+                }
+            };
+            let keyring = string("keyring")?
+                .ok_or_else(|| error(Some("signatures.keyring"), "is missing".into()))?;
+            let keyring = keyring_path(&jargo_home, keyring);
+            match fs::read(&keyring) {
+                Ok(keys) if keys.starts_with(b"-----BEGIN") => {
+                    return Err(error(
+                        Some("signatures.keyring"),
+                        format!(
+                            "{} is armored, export the keys without --armor",
+                            keyring.display()
+                        ),
+                    ))
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(error(
+                        Some("signatures.keyring"),
+                        format!("can't read {}: {}", keyring.display(), e),
+                    ))
+                }
+            }
+            config.signatures = Some(SignatureConfig {
+                keyring,
+                policy: policy("policy")?,
+                key_changes: policy("key-changes")?,
+            });
+        }
+    }
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+/// the keyring relative to ~/jargo, gpgv needs an absolute path
+fn keyring_path(jargo_home: &Path, keyring: &str) -> PathBuf {
+    match keyring.strip_prefix("~/") {
+        Some(keyring) => jargo_home.parent().unwrap_or(jargo_home).join(keyring),
+        None => jargo_home.join(keyring),
+    }
+}
@@ -1,19 +1,25 @@
+use std::collections::HashSet;
 use std::fs;
-use std::fs::{create_dir, create_dir_all, File};
-use std::io::prelude::*;
-use std::io::Write;
+use std::fs::create_dir_all;
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, Error};
+use anyhow::Error;
 use bytes::Bytes;
-use sha1::{Digest, Sha1};
 use strong_xml::XmlRead;
+use toml::Table;
 
+use crate::cache::{self, CacheLock};
+use crate::checksum::{self, Algorithm, ChecksumPolicy};
 use crate::config::config;
+use crate::error::JargoError;
 use crate::maven::metadata::Metadata;
 use crate::maven::pom::Pom;
-use crate::project::{Artifact, Project};
+use crate::project::{Artifact, Project, Repository};
+use crate::signature::{self, Verification};
+use crate::version::VersionRange;
 use colored::Colorize;
+use reqwest::blocking::Response;
 use reqwest::StatusCode;
 use crate::maven::pom_view::PomView;
 
@@ -28,15 +34,120 @@ use crate::maven::pom_view::PomView;
 /// 7. if not downloads it from a repo (now mavencentral only)
 /// 8. verifies the SHA1 as for the jar
 /// 9. extracts the transitive dependencies from the pom and recurses to (1) for the list of dependencies
-pub fn load(project: &Project) -> Result<(), Error> {
-    load_artifacts(project, &project.main_dependencies)?;
-    load_artifacts(project, &project.test_dependencies)?;
-    Ok(())
+///
+/// Returns the local paths of all jars that were resolved, split in main and test,
+/// and the annotation processors, that are resolved on their own
+pub fn load(project: &Project) -> Result<Classpath, Error> {
+    load_with(project, &[], &[])
+}
+
+/// Like load, together with the dependencies of the projects that this project depends on.
+/// Those come after its own dependencies, so the versions of the project itself win.
+pub fn load_with(
+    project: &Project,
+    inherited_main: &[Artifact],
+    inherited_test: &[Artifact],
+) -> Result<Classpath, Error> {
+    let with_inherited = |own: &[Artifact], inherited: &[Artifact]| -> Vec<Artifact> {
+        own.iter().chain(inherited).cloned().collect()
+    };
+    let required_by = [coordinates(&project.group, &project.name, &project.version)];
+    let mut seen = HashSet::new();
+    let mut main = Resolved::default();
+    load_artifacts(
+        project,
+        &with_inherited(&project.main_dependencies, inherited_main),
+        &required_by,
+        &mut seen,
+        &mut main,
+    )?;
+    let mut test = Resolved::default();
+    load_artifacts(
+        project,
+        &with_inherited(&project.test_dependencies, inherited_test),
+        &required_by,
+        &mut seen,
+        &mut test,
+    )?;
+    let mut processors = Resolved::default();
+    load_artifacts(
+        project,
+        &project.annotation_processors,
+        &required_by,
+        &mut HashSet::new(),
+        &mut processors,
+    )?;
+    Ok(Classpath {
+        main: main.jars,
+        test: test.jars,
+        processors: processors.jars,
+        artifacts: [main.artifacts, test.artifacts, processors.artifacts].concat(),
+        signers: [main.signers, test.signers, processors.signers].concat(),
+    })
+}
+
+/// Loads an artifact that jargo itself needs, like a test launcher, together with its dependencies
+pub fn load_tool(project: &Project, artifact: Artifact) -> Result<Vec<String>, Error> {
+    let mut resolved = Resolved::default();
+    load_artifacts(
+        project,
+        &vec![artifact],
+        &[String::from("jargo")],
+        &mut HashSet::new(),
+        &mut resolved,
+    )?;
+    Ok(resolved.jars)
+}
+
+/// the jars and the artifacts they belong to, together with the artifacts without a jar
+#[derive(Debug, Default)]
+struct Resolved {
+    jars: Vec<String>,
+    artifacts: Vec<Artifact>,
+    signers: Vec<(String, String)>,
+}
+
+/// The resolved jars in the local cache.
+/// The main jars are needed at compile time and at runtime, the test jars only for the tests.
+#[derive(Debug, Default, Clone)]
+pub struct Classpath {
+    pub main: Vec<String>,
+    pub test: Vec<String>,
+    /// the -processorpath, annotation processors are not on the classpath
+    pub processors: Vec<String>,
+    /// all artifacts that were resolved, for Jargo.lock
+    pub artifacts: Vec<Artifact>,
+    /// the group and the key fingerprint of the artifacts with a verified signature, for Jargo.lock
+    pub signers: Vec<(String, String)>,
+}
+
+impl Classpath {
+    /// the jars needed to run the application
+    pub fn runtime(&self) -> &[String] {
+        &self.main
+    }
+
+    /// the jars needed to compile and run the tests
+    pub fn test_runtime(&self) -> Vec<String> {
+        self.main.iter().chain(self.test.iter()).cloned().collect()
+    }
 }
 
-fn load_artifacts(project: &Project, artifacts: &Vec<Artifact>) -> Result<(), Error> {
+/// The first version that is encountered for a group:name wins, like in maven (nearest wins).
+/// This also prevents endless recursion on cyclic dependencies.
+///
+/// `required_by` are the artifacts that lead to these, nearest first, for the error messages.
+fn load_artifacts(
+    project: &Project,
+    artifacts: &Vec<Artifact>,
+    required_by: &[String],
+    seen: &mut HashSet<String>,
+    resolved: &mut Resolved,
+) -> Result<(), Error> {
     for art in artifacts {
-        load_artifact(project, art)?;
+        if seen.insert(format!("{}:{}", art.group, art.name)) {
+            load_artifact(project, art, required_by, seen, resolved)?;
+        }
     }
     Ok(())
 }
@@ -45,7 +156,25 @@ fn load_artifacts(project: &Project, artifacts: &Vec<Artifact>) -> Result<(), Er
 /// 1. create dir in local cache if necessary
 /// 2. look up the pom
 /// 3. look up the jar
-fn load_artifact(project: &Project, artifact: &Artifact) -> Result<(), Error> {
+fn load_artifact(
+    project: &Project,
+    artifact: &Artifact,
+    required_by: &[String],
+    seen: &mut HashSet<String>,
+    resolved: &mut Resolved,
+) -> Result<(), Error> {
+    // a range is resolved first, then it is like any other version
+    let artifact = &if VersionRange::is_range(artifact.version.as_str()) {
+        resolve_range(project, artifact).map_err(|e| with_required_by(e, required_by))?
+    } else {
+        artifact.clone()
+    };
+    resolved.artifacts.push(artifact.clone());
+    let this = coordinates(&artifact.group, &artifact.name, artifact.version.as_str());
+    let with_this: Vec<String> = [this.clone()]
+        .into_iter()
+        .chain(required_by.to_vec())
+        .collect();
     // check/create artifact directory
     let local_artifact_loc = format!("{}/{}", config().cache_location, artifact.path);
     if !exists(&local_artifact_loc) {
@@ -53,122 +182,195 @@ fn load_artifact(project: &Project, artifact: &Artifact) -> Result<(), Error> {
     }
 
     // download remote pom if not in cache
-    let pom_lookup = lookup_verified_pom(project, artifact)?;
+    let pom_lookup =
+        lookup_verified_pom(project, artifact).map_err(|e| with_required_by(e, required_by))?;
 
-    // download remote jar if not in cache and check its SHA-1 checksum
-    let local_artifact_jar_path = format!(
-        "{}/{}-{}.jar",
-        local_artifact_loc, artifact.name, artifact.version
-    );
-    if !exists(&local_artifact_jar_path) {
-        lookup_verified_jar(
-            project,
-            artifact,
-            &local_artifact_jar_path,
-            &pom_lookup.resolved_repo.unwrap(),
-            &pom_lookup.resolved_version.unwrap(),
-        )?;
-    }
-
-    println!("{}", pom_lookup.pom_xml);
     // parse pom file
-    let pom = Pom::from_str(&pom_lookup.pom_xml).unwrap();
-    let pom = PomView::new(pom, project)?;
+    let pom = Pom::from_str(&pom_lookup.pom_xml).map_err(|e| JargoError::Resolution {
+        coordinates: this.clone(),
+        dependency_path: required_by.to_vec(),
+        message: format!("invalid pom: {}", e),
+    })?;
+    // the parents are needed by this artifact
+    let pom = PomView::new(pom, project).map_err(|e| with_required_by(e, &with_this))?;
+
+    // download remote jar if not in cache and check its checksum and signature
+    // artifacts with packaging 'pom' (parents, boms, starters) don't have a jar
+    let signer = if !pom.is_pom_packaging() {
+        let local_artifact_jar_path = format!(
+            "{}/{}-{}.jar",
+            local_artifact_loc, artifact.name, pom_lookup.resolved_version
+        );
+        let lock = CacheLock::acquire(&local_artifact_loc)?;
+        // a cached jar that doesn't match its checksum anymore is removed and downloaded again
+        let cached = read_cached(&local_artifact_jar_path, project.checksums)?;
+        let signer = match cached {
+            None => {
+                // when the pom came from the cache, the repository of the jar is not known
+                let repositories = match &pom_lookup.resolved_repo {
+                    Some(repo) => vec![repo.clone()],
+                    None => project.repositories.clone(),
+                };
+                lookup_verified_jar(
+                    artifact,
+                    &local_artifact_jar_path,
+                    &repositories,
+                    &pom_lookup.resolved_version,
+                )
+            }
+            // the signature is checked every time, to know the signer
+            Some(jar) if config().signatures.is_some() => {
+                verify_signature(&jar, None, &local_artifact_jar_path)
+            }
+            Some(_) => Ok(None),
+        }
+        .map_err(|e| with_required_by(e, required_by))?;
+        drop(lock);
+        resolved.jars.push(local_artifact_jar_path);
+        signer
+    } else {
+        pom_lookup.signer
+    };
+    if let Some(signer) = signer {
+        resolved.signers.push((artifact.group.clone(), signer));
+    }
 
     //TODO exclusions
 
-    let artifacts = pom.dependencies().into_iter().map(|d| d.into()).collect();
-    load_artifacts(project, &artifacts)?;
+    let artifacts = pom
+        .dependencies()
+        .map_err(|e| with_required_by(e.into(), &with_this))?
+        .into_iter()
+        .map(|d| d.into())
+        .collect();
+    load_artifacts(project, &artifacts, &with_this, seen, resolved)?;
 
     Ok(())
 }
 
+/// group:name:version, as in the error messages
+fn coordinates(group: &str, name: &str, version: &str) -> String {
+    format!("{}:{}:{}", group, name, version)
+}
+
+/// adds the dependency path to a resolution error that doesn't have it yet
+fn with_required_by(error: Error, required_by: &[String]) -> Error {
+    match error.downcast::<JargoError>() {
+        Ok(JargoError::Resolution {
+            coordinates,
+            dependency_path,
+            message,
+        }) if dependency_path.is_empty() => JargoError::Resolution {
+            coordinates,
+            dependency_path: required_by.to_vec(),
+            message,
+        }
+        .into(),
+        Ok(error) => error.into(),
+        Err(error) => error,
+    }
+}
+
 /// main function to download and verify the pom xml.
-/// 1. check if file is locally cached and load if it is
-/// 2. or find a suitable repo (deferred to find_pom)
-/// 3. this function returns the downloaded pom, together with the repo it was found in and the "resolved version'
-/// this is only applicable to SNAPSHOT's where x-SNAPSHOT is resolved to x-<timestamp>-<build_nr>
-/// 4. download the SHA1 file from the same location
-/// 5. validate if the checksum equals the checksum calculated from the pom
+/// 1. a SNAPSHOT is resolved to x-<timestamp>-<build_nr> first, see resolve_snapshot
+/// 2. check if file is locally cached and load if it is
+/// 3. or find a suitable repo (deferred to find_pom)
+/// 4. this function returns the pom, together with the repo it was found in and the "resolved version'
+/// 5. a download is verified with the strongest checksum in the repo, see verify_download,
+///    a cached pom with the checksum that was stored with it
 ///
-/// The result from find_pom is passed on to the caller so that the information can be used
+/// The directory of the artifact is locked meanwhile, so that jargo processes can share the cache.
+///
+/// The result is passed on to the caller so that the information can be used
 /// for subsequent requests.
 pub(crate) fn lookup_verified_pom(
     project: &Project,
     artifact: &Artifact,
 ) -> Result<PomLookupResult, Error> {
+    // another jargo process may be downloading it
+    let local_artifact_dir = format!("{}/{}", config().cache_location, artifact.path);
+    let _lock = CacheLock::acquire(&local_artifact_dir)?;
+    cache::stamp(&local_artifact_dir)?;
+
+    // a snapshot is stored under its unique version, so that a new one doesn't overwrite it
+    let snapshot = if artifact.is_snapshot() {
+        resolve_snapshot(project, artifact, &local_artifact_dir)?
+    } else {
+        None
+    };
+    let (repositories, resolved_version) = match &snapshot {
+        Some((repo, version)) => (vec![repo.clone()], version.clone()),
+        None => (project.repositories.clone(), artifact.version.to_string()),
+    };
     let local_artifact_pom_path = &format!(
-        "{}/{}/{}-{}.pom",
-        config().cache_location, artifact.path, artifact.name, artifact.version
+        "{}/{}-{}.pom",
+        local_artifact_dir, artifact.name, resolved_version
     );
     // get pom from local or remote
-    let result = if exists(local_artifact_pom_path) {
-        read_file_to_string(local_artifact_pom_path)?
-    } else {
-        find_pom(project, artifact, local_artifact_pom_path)?
+    let result = match read_cached(local_artifact_pom_path, project.checksums)? {
+        Some(pom_xml) => {
+            let signer = verify_signature(&pom_xml, None, local_artifact_pom_path)?;
+            Some(PomLookupResult {
+                pom_xml: String::from_utf8(pom_xml)?,
+                resolved_repo: snapshot.map(|(repo, _)| repo),
+                resolved_version,
+                signer,
+            })
+        }
+        None => find_pom(
+            artifact,
+            &resolved_version,
+            local_artifact_pom_path,
+            &repositories,
+        )?,
     };
-    if result.is_none() {
-        panic!("Could not find pom for {}", artifact.path)
-    } else {
-        let result = result.unwrap();
-        let repo_with_pom = result.resolved_repo.as_ref();
-        let pom_xml = &result.pom_xml;
-
-        let local_artifact_pom_sha1_path = format!("{}.sha1", local_artifact_pom_path);
-
-        // verify jarfile with SHA1 checksum (which is hex encoded)
-        let checksum = if !exists(&local_artifact_pom_sha1_path) {
-            download_checksum(repo_with_pom, &local_artifact_pom_sha1_path)?
-        } else {
-            read_file_to_bytes(local_artifact_pom_sha1_path)?
-        };
-        if let Some(checksum) = checksum {
-            let validated = validate_checksum_text(&pom_xml, hex::decode(checksum)?);
-            return if !validated {
-                Err(anyhow!("SHA1 checksum for {} is not valid", artifact.path))
-            } else {
-                Ok(result.clone()) // SHA1 ok
-            };
-        } else {
-            // no SHA1 found
-            Ok(result.clone())
+    result.ok_or_else(|| {
+        JargoError::Resolution {
+            coordinates: coordinates(&artifact.group, &artifact.name, artifact.version.as_str()),
+            dependency_path: vec![],
+            message: format!("no pom found in {}", urls(&repositories)),
         }
-    }
+        .into()
+    })
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct PomLookupResult {
     pub(crate) pom_xml: String,
-    pub(crate) resolved_repo: Option<String>,
-    pub(crate) resolved_version: Option<String>,
+    pub(crate) resolved_repo: Option<Repository>,
+    /// the version, or the unique version of a snapshot, like 1.0-20240101.120000-5
+    pub(crate) resolved_version: String,
+    /// the fingerprint of the key that signed the pom
+    pub(crate) signer: Option<String>,
 }
 
 fn find_pom(
-    project: &Project,
     artifact: &Artifact,
+    resolved_version: &str,
     local_artifact_pom_path: &str,
+    repositories: &[Repository],
 ) -> Result<Option<PomLookupResult>, Error> {
-    for repo in &project.repositories {
-        let resolved_version = resolve_version(&artifact, repo)?;
-        let r = download_pom(artifact, &resolved_version, local_artifact_pom_path, &repo)?;
-        if r.is_some() {
+    for repo in repositories {
+        let r = download_pom(artifact, resolved_version, local_artifact_pom_path, repo)?;
+        if let Some((pom_xml, signer)) = r {
             return Ok(Some(PomLookupResult {
-                pom_xml: r.unwrap(),
+                pom_xml,
                 resolved_repo: Some(repo.clone()),
-                resolved_version: Some(resolved_version),
+                resolved_version: resolved_version.into(),
+                signer,
             }));
         }
     }
     Ok(None)
 }
 
-/// returns the pom and the repo where it was found
+/// returns the pom and the fingerprint of its signer, when the repo has it
 fn download_pom(
     artifact: &Artifact,
     resolved_version: &str,
     local_artifact_pom_path: &str,
-    repo: &str,
-) -> Result<Option<String>, Error> {
+    repo: &Repository,
+) -> Result<Option<(String, Option<String>)>, Error> {
     // can't assume it exists
     let local_artifact_dir = format!("{}/{}", config().cache_location, artifact.path);
 
@@ -177,181 +379,486 @@ fn download_pom(
     }
     let remote_artifact_pom_url = format!(
         "{}/{}/{}-{}.pom",
-        repo, artifact.path, artifact.name, resolved_version
+        repo.url, artifact.path, artifact.name, resolved_version
     );
 
     println!("{} {}", "Downloading".green(), remote_artifact_pom_url);
-    let response = reqwest::blocking::get(&remote_artifact_pom_url)?;
+    let response = get(&remote_artifact_pom_url)?;
     if response.status().is_success() {
-        let body = response.text().unwrap();
+        let body = response
+            .text()
+            .map_err(|e| network_error(&remote_artifact_pom_url, e))?;
         println!("{} {}", "Downloaded".green(), remote_artifact_pom_url);
+        verify_download(
+            body.as_bytes(),
+            &remote_artifact_pom_url,
+            local_artifact_pom_path,
+            repo.checksums,
+        )?;
+        let signer = verify_signature(
+            body.as_bytes(),
+            Some(&remote_artifact_pom_url),
+            local_artifact_pom_path,
+        )?;
         write_text(local_artifact_pom_path, &body)?;
-        Ok(Some((body)))
+        Ok(Some((body, signer)))
     } else {
         Ok(None)
     }
 }
 
-/// Download jar from remote repo and check its signature
+/// Download jar from the first remote repo that has it and verify its checksum and signature.
+/// Returns the fingerprint of the signer.
 /// For now it's a blocking call, because async and recursion add unwanted complexity/I don't understand that
 /// TODO add progress bar
 fn lookup_verified_jar(
-    project: &Project,
     artifact: &Artifact,
     local_artifact_jar_path: &str,
-    resolved_repo: &str,
+    repositories: &[Repository],
     resolved_version: &str,
-) -> Result<(), Error> {
-    let remote_artifact_jar_url = format!(
-        "{}/{}/{}-{}.jar",
-        resolved_repo, artifact.path, artifact.name, resolved_version
-    );
-
-    println!("{} {}", "Downloading".green(), remote_artifact_jar_url);
-    let response = reqwest::blocking::get(&remote_artifact_jar_url)?;
-    if response.status().is_success() {
-        let jar = response.bytes().unwrap();
+) -> Result<Option<String>, Error> {
+    for repo in repositories {
+        let remote_artifact_jar_url = format!(
+            "{}/{}/{}-{}.jar",
+            repo.url, artifact.path, artifact.name, resolved_version
+        );
+
+        println!("{} {}", "Downloading".green(), remote_artifact_jar_url);
+        let response = get(&remote_artifact_jar_url)?;
+        if !response.status().is_success() {
+            continue;
+        }
+        let jar = response
+            .bytes()
+            .map_err(|e| network_error(&remote_artifact_jar_url, e))?;
         println!("{} {}", "Downloaded".green(), remote_artifact_jar_url);
-        write_bytes_to_file(&local_artifact_jar_path, &jar)?;
-
-        let local_artifact_jar_sha1_path = format!("{}.sha1", local_artifact_jar_path);
-
-        // verify jarfile with SHA1 checksum (which is hex encoded)
-        let checksum = if !exists(&local_artifact_jar_sha1_path) {
-            download_checksum(
-                Some(&remote_artifact_jar_url),
-                &local_artifact_jar_sha1_path,
-            )?
-        } else {
-            read_file_to_bytes(local_artifact_jar_sha1_path)?
-        };
-        return if let Some(checksum) = checksum {
-            let validated = validate_checksum_bytes(&jar, hex::decode(checksum)?);
-            if !validated {
-                Err(anyhow!(
-                    "SHA1 checksum for {} is not valid",
-                    remote_artifact_jar_url
-                ))
-            } else {
-                Ok(()) // checksum found and ok
-            }
-        } else {
-            Ok(()) // no checksum found
-        };
+        // verified before it is written, so that a bad jar never ends up in the cache
+        verify_download(
+            &jar,
+            &remote_artifact_jar_url,
+            local_artifact_jar_path,
+            repo.checksums,
+        )?;
+        let signer = verify_signature(
+            &jar,
+            Some(&remote_artifact_jar_url),
+            local_artifact_jar_path,
+        )?;
+        write_bytes_to_file(local_artifact_jar_path, &jar)?;
+        return Ok(signer);
     }
-    panic!(
-        "Artifact {} not found in remote repository {}",
-        artifact.path, resolved_repo
-    );
+    Err(JargoError::Resolution {
+        coordinates: coordinates(&artifact.group, &artifact.name, artifact.version.as_str()),
+        dependency_path: vec![],
+        message: format!("no jar found in {}", urls(repositories)),
+    }
+    .into())
 }
 
-fn resolve_version(artifact: &Artifact, repo: &String) -> Result<String, Error> {
-    Ok(if artifact.is_snapshot() {
-        let build_nr = load_snapshot_build_nr(&artifact.path, repo)?;
-        if let Some(build_nr) = build_nr {
-            artifact.version.replace("SNAPSHOT", &build_nr)
-        } else {
-            artifact.version.clone()
-        }
-    } else {
-        artifact.version.clone()
-    })
+fn urls(repositories: &[Repository]) -> String {
+    repositories
+        .iter()
+        .map(|r| r.url.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Snapshots in a maven repo can be in the form
 /// 'spring-boot-starter-web-3.0.0-20221124.170206-1099.jar'
 /// while we ask for
 /// 'spring-boot-starter-web-3.0.0-SNAPSHOT.jar'
-/// the metadata xml contains the info on what snapshot to download
-/// so we download and parse it
-fn load_snapshot_build_nr(artifact_path: &str, repo: &String) -> Result<Option<String>, Error> {
-    let metadata_url = format!("{}/{}/maven-metadata.xml", repo, artifact_path);
-    let response = reqwest::blocking::get(&metadata_url)?;
-    if response.status().is_success() {
-        let body = response.text().unwrap();
-        write_text(
-            format!(
-                "{}/{}/maven-metadata.xml",
-                config().cache_location,
-                artifact_path
-            )
-                .as_str(),
-            &body,
-        )?;
-        let metadata = Metadata::from_str(&body)?;
-        Ok(Some(format!(
+/// the metadata xml contains the info on what snapshot to download.
+///
+/// Returns the first repo with metadata for the snapshot, with the unique version.
+/// Returns None when no repo has metadata, then the snapshot is looked up as x-SNAPSHOT.
+fn resolve_snapshot(
+    project: &Project,
+    artifact: &Artifact,
+    local_artifact_dir: &str,
+) -> Result<Option<(Repository, String)>, Error> {
+    let this = coordinates(&artifact.group, &artifact.name, artifact.version.as_str());
+    let metadata = repository_metadata(
+        project,
+        &this,
+        &artifact.path,
+        local_artifact_dir,
+        MetadataKind::Snapshot,
+    )?;
+    Ok(metadata.into_iter().next().and_then(|(repo, metadata)| {
+        let snapshot = metadata.versioning.snapshot?;
+        let build_nr = format!(
             "{}-{}",
-            metadata.versioning.snapshot.timestamp.value,
-            metadata.versioning.snapshot.build_number.value
-        )))
+            snapshot.timestamp.value, snapshot.build_number.value
+        );
+        let version = artifact.version.as_str();
+        let base = &version[..version.len() - "SNAPSHOT".len()];
+        Some((repo, format!("{}{}", base, build_nr)))
+    }))
+}
+
+/// A version range, like [1.0,2.0) or ^1.2, is resolved to the highest version in it that is
+/// in the maven-metadata.xml of the artifact (next to its version directories) in any repo.
+fn resolve_range(project: &Project, artifact: &Artifact) -> Result<Artifact, Error> {
+    let this = coordinates(&artifact.group, &artifact.name, artifact.version.as_str());
+    let resolution_error = |message: String| JargoError::Resolution {
+        coordinates: this.clone(),
+        dependency_path: vec![],
+        message,
+    };
+    let range = VersionRange::parse(artifact.version.as_str())
+        .map_err(|e| resolution_error(e.to_string()))?;
+    let remote_path = format!("{}/{}", artifact.group.replace('.', "/"), artifact.name);
+    let local_dir = format!("{}/{}", config().cache_location, remote_path);
+    let _lock = CacheLock::acquire(&local_dir)?;
+    let metadata = repository_metadata(
+        project,
+        &this,
+        &remote_path,
+        &local_dir,
+        MetadataKind::Versions,
+    )?;
+    let versions: Vec<&str> = metadata.iter().flat_map(|(_, m)| m.versions()).collect();
+    match range.select(versions.iter().copied()) {
+        Some(version) => Ok(Artifact::new(&artifact.group, &artifact.name, version)),
+        None if versions.is_empty() => Err(resolution_error(format!(
+            "no versions found in {}",
+            urls(&project.repositories)
+        ))
+        .into()),
+        None => Err(resolution_error(format!(
+            "no version matches, available are {}",
+            versions.join(", ")
+        ))
+        .into()),
+    }
+}
+
+/// which maven-metadata.xml is read
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetadataKind {
+    /// the one of a snapshot, from the first repo that has it
+    Snapshot,
+    /// the one with the versions of an artifact, from all repos that have it
+    Versions,
+}
+
+/// The maven-metadata.xml in `remote_path` of the repos that have it. It is kept per repo as
+/// maven-metadata-<repo>.xml in `local_dir`, and only downloaded again when the snapshot or
+/// release update policy of the repo says so. The last checks are in resolver-status.toml.
+fn repository_metadata(
+    project: &Project,
+    coordinates: &str,
+    remote_path: &str,
+    local_dir: &str,
+    kind: MetadataKind,
+) -> Result<Vec<(Repository, Metadata)>, Error> {
+    let status_path = format!("{}/resolver-status.toml", local_dir);
+    let mut status = if exists(&status_path) {
+        fs::read_to_string(&status_path)?
+            .parse::<Table>()
+            .unwrap_or_default()
     } else {
-        Ok(None)
+        Table::new()
+    };
+    let now = SystemTime::now();
+    let mut found = vec![];
+    for repo in &project.repositories {
+        let local_metadata_path = format!("{}/maven-metadata-{}.xml", local_dir, repo.name);
+        let last_checked = status
+            .get(&repo.name)
+            .and_then(toml::Value::as_integer)
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs as u64));
+        let policy = match kind {
+            MetadataKind::Snapshot => repo.snapshots,
+            MetadataKind::Versions => repo.releases,
+        };
+        if policy.is_due(last_checked, now) {
+            let metadata_url = format!("{}/{}/maven-metadata.xml", repo.url, remote_path);
+            match download_metadata(&metadata_url) {
+                Ok(body) => {
+                    match body {
+                        Some(body) => write_text(&local_metadata_path, &body)?,
+                        None if exists(&local_metadata_path) => {
+                            fs::remove_file(&local_metadata_path)?
+                        }
+                        None => {}
+                    }
+                    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                    status.insert(repo.name.clone(), toml::Value::Integer(secs as i64));
+                }
+                // like maven, offline builds go on with what was downloaded before
+                Err(e) if exists(&local_metadata_path) => {
+                    println!(
+                        "{}: {}, using the maven-metadata.xml that was downloaded before",
+                        "warning".yellow().bold(),
+                        e
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if exists(&local_metadata_path) {
+            let metadata =
+                Metadata::from_str(&fs::read_to_string(&local_metadata_path)?).map_err(|e| {
+                    JargoError::Resolution {
+                        coordinates: coordinates.into(),
+                        dependency_path: vec![],
+                        message: format!("invalid maven-metadata.xml in {}: {}", repo.url, e),
+                    }
+                })?;
+            found.push((repo.clone(), metadata));
+            if kind == MetadataKind::Snapshot {
+                break;
+            }
+        }
+    }
+    cache::write_atomic(&status_path, toml::to_string(&status)?.as_bytes())?;
+    Ok(found)
+}
+
+/// the maven-metadata.xml at the url, None when the repo doesn't have it
+fn download_metadata(url: &str) -> Result<Option<String>, JargoError> {
+    let response = get(url)?;
+    let status = response.status();
+    if status.is_server_error() {
+        return Err(JargoError::Network {
+            url: url.into(),
+            message: status.to_string(),
+        });
+    }
+    if !status.is_success() {
+        return Ok(None);
+    }
+    response.text().map(Some).map_err(|e| network_error(url, e))
+}
+
+fn get(url: &str) -> Result<Response, JargoError> {
+    reqwest::blocking::get(url).map_err(|e| network_error(url, e))
+}
+
+fn network_error(url: &str, error: reqwest::Error) -> JargoError {
+    JargoError::Network {
+        url: url.into(),
+        message: error.to_string(),
     }
 }
 
-fn download_checksum(
-    remote_artifact_url: Option<&String>,
-    local_artifact_jar_sha1_path: &str,
-) -> Result<Option<Vec<u8>>, Error> {
-    if let Some(remote_artifact_url) = remote_artifact_url {
-        let remote_artifact_jar_sha1_url = format!("{}.sha1", remote_artifact_url);
-        let response = reqwest::blocking::get(&remote_artifact_jar_sha1_url)?;
+/// Verifies a download with the strongest checksum that the repo has for it, and stores that
+/// checksum next to the file in the cache. The policy of the repo decides what happens when
+/// there is none, or when it doesn't match.
+fn verify_download(
+    contents: &[u8],
+    url: &str,
+    local_path: &str,
+    policy: ChecksumPolicy,
+) -> Result<(), Error> {
+    if policy == ChecksumPolicy::Ignore {
+        return Ok(());
+    }
+    let mut checksum = None;
+    for algorithm in Algorithm::STRONGEST_FIRST {
+        let checksum_url = format!("{}.{}", url, algorithm.extension());
+        let response = get(&checksum_url)?;
         if response.status() == StatusCode::OK {
-            let jar_checksum = response.bytes().unwrap();
-            write_bytes_to_file(&local_artifact_jar_sha1_path, &jar_checksum)?;
-            Ok(Some(jar_checksum.to_vec()))
-        } else {
+            let checksum_file = response
+                .bytes()
+                .map_err(|e| network_error(&checksum_url, e))?;
+            checksum = Some((algorithm, checksum_file));
+            break;
+        }
+    }
+    let expected = checksum
+        .as_ref()
+        .map(|(algorithm, file)| (*algorithm, checksum::parse(file)));
+    match verdict(policy, contents, expected, url, local_path) {
+        Verdict::Verified => {
+            if let Some((algorithm, checksum_file)) = checksum {
+                let local_checksum_path = format!("{}.{}", local_path, algorithm.extension());
+                write_bytes_to_file(&local_checksum_path, &checksum_file)?;
+            }
+            Ok(())
+        }
+        Verdict::Unchecked => Ok(()),
+        Verdict::Warn(error) => {
+            println!("{}: {}", "warning".yellow().bold(), error);
+            Ok(())
+        }
+        Verdict::Reject(error) => Err(error.into()),
+    }
+}
+
+/// Reads a file from the cache and verifies it with the strongest checksum that was stored next
+/// to it. None when it's not there, or when the policy rejects it: then it's removed, so that it
+/// is downloaded again.
+fn read_cached(local_path: &str, policy: ChecksumPolicy) -> Result<Option<Vec<u8>>, Error> {
+    if !exists(local_path) {
+        return Ok(None);
+    }
+    let contents = fs::read(local_path)?;
+    // without one, the policy was applied when it was downloaded
+    let Some(checksum) = cache::stored_checksum(Path::new(local_path))? else {
+        return Ok(Some(contents));
+    };
+    match verdict(policy, &contents, Some(checksum), local_path, local_path) {
+        Verdict::Verified | Verdict::Unchecked => Ok(Some(contents)),
+        Verdict::Warn(error) => {
+            println!("{}: {}", "warning".yellow().bold(), error);
+            Ok(Some(contents))
+        }
+        Verdict::Reject(error) => {
+            fs::remove_file(local_path)?;
+            println!(
+                "{}: {}, downloading it again",
+                "warning".yellow().bold(),
+                error
+            );
             Ok(None)
         }
-    } else {
-        Ok(None)
     }
 }
 
-fn validate_checksum_bytes(jar: &Bytes, checksum: Vec<u8>) -> bool {
-    let mut hasher = Sha1::new();
-    hasher.update(&jar.to_vec());
-    let result = hasher.finalize();
-    result[..] == checksum
+/// what the checksum policy does with a file
+#[derive(Debug)]
+enum Verdict {
+    /// it matches its checksum
+    Verified,
+    /// it's not checked
+    Unchecked,
+    /// it's used, with a warning about the missing or wrong checksum
+    Warn(JargoError),
+    /// it's not used
+    Reject(JargoError),
+}
+
+/// Decides what happens to a file, given the strongest checksum that is known for it.
+fn verdict(
+    policy: ChecksumPolicy,
+    contents: &[u8],
+    checksum: Option<(Algorithm, String)>,
+    url: &str,
+    local_path: &str,
+) -> Verdict {
+    if policy == ChecksumPolicy::Ignore {
+        return Verdict::Unchecked;
+    }
+    let error = match checksum {
+        None => JargoError::MissingChecksum { url: url.into() },
+        Some((algorithm, expected)) => {
+            let actual = algorithm.hash(contents);
+            if actual == expected {
+                return Verdict::Verified;
+            }
+            JargoError::Checksum {
+                url: url.into(),
+                algorithm: algorithm.extension().into(),
+                file: local_path.into(),
+                expected,
+                actual,
+            }
+        }
+    };
+    match policy {
+        ChecksumPolicy::Fail => Verdict::Reject(error),
+        ChecksumPolicy::Warn | ChecksumPolicy::Ignore => Verdict::Warn(error),
+    }
 }
 
-fn validate_checksum_text(text: &str, checksum: Vec<u8>) -> bool {
-    let mut hasher = Sha1::new();
-    hasher.update(text.as_bytes());
-    let result = hasher.finalize();
-    result[..] == checksum
+/// Verifies the PGP signature of a download, or of a file in the cache when there is no url,
+/// with the keyring in the global config. The signature is downloaded to the cache, next to
+/// the file. Returns the fingerprint of the key that signed it, None when signatures are not
+/// checked, or when the policy accepts it without a valid signature.
+fn verify_signature(
+    contents: &[u8],
+    url: Option<&str>,
+    local_path: &str,
+) -> Result<Option<String>, Error> {
+    let Some(signatures) = &config().signatures else {
+        return Ok(None);
+    };
+    let local_signature_path = format!("{}.asc", local_path);
+    if let Some(url) = url {
+        let signature_url = format!("{}.asc", url);
+        let response = get(&signature_url)?;
+        if response.status() == StatusCode::OK {
+            let signature = response
+                .bytes()
+                .map_err(|e| network_error(&signature_url, e))?;
+            write_bytes_to_file(&local_signature_path, &signature)?;
+        } else if exists(&local_signature_path) {
+            fs::remove_file(&local_signature_path)?;
+        }
+    }
+    let url = url.unwrap_or(local_path);
+    if !exists(&local_signature_path) {
+        signatures.policy.apply(JargoError::Signature {
+            url: url.into(),
+            message: "there is no .asc signature".into(),
+        })?;
+        return Ok(None);
+    }
+    match signature::verify(
+        contents,
+        Path::new(&local_signature_path),
+        &signatures.keyring,
+    )? {
+        Verification::Signed(fingerprint) => Ok(Some(fingerprint)),
+        Verification::Invalid(message) => {
+            signatures.policy.apply(JargoError::Signature {
+                url: url.into(),
+                message,
+            })?;
+            Ok(None)
+        }
+    }
 }
 
+/// an error with the fail policy, a warning with the warn policy
 fn exists(path: &str) -> bool {
     Path::new(path).exists()
 }
 
 fn write_bytes_to_file(jar_path: &str, bytes: &Bytes) -> Result<(), Error> {
-    let mut file = File::create(jar_path)?;
-    file.write_all(bytes)?;
+    cache::write_atomic(jar_path, bytes)?;
     Ok(())
 }
 
 fn write_text(path: &str, contents: &String) -> Result<(), Error> {
-    let mut file = File::create(path)?;
-    file.write(contents.as_bytes())?;
+    cache::write_atomic(path, contents.as_bytes())?;
     Ok(())
 }
 
-fn read_file_to_string(local_artifact_pom_path: &str) -> Result<Option<PomLookupResult>, Error> {
-    let mut file = File::open(local_artifact_pom_path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    Ok(Some(PomLookupResult {
-        pom_xml: contents,
-        resolved_repo: None,
-        resolved_version: None,
-    }))
-}
-
-fn read_file_to_bytes(local_artifact_jar_sha1_path: String) -> Result<Option<Vec<u8>>, Error> {
-    let mut file = File::open(local_artifact_jar_sha1_path)?;
-    let mut contents = Vec::new();
-    file.read_to_end(&mut contents)?;
-    Ok(Some(contents))
+#[cfg(test)]
+mod test {
+    use crate::checksum::Algorithm;
+    use crate::checksum::ChecksumPolicy::{Fail, Ignore, Warn};
+    use crate::deploader::{verdict, Verdict};
+
+    #[test]
+    fn checksum_policies() {
+        let jar = b"jar";
+        let sha1 = Algorithm::Sha1.hash(jar);
+        let missing = None;
+        let matching = Some((Algorithm::Sha1, sha1));
+        let wrong = Some((Algorithm::Sha1, "0".repeat(40)));
+        let cases = [
+            (Fail, missing.clone(), "reject"),
+            (Fail, wrong.clone(), "reject"),
+            (Fail, matching.clone(), "verified"),
+            (Warn, missing.clone(), "warn"),
+            (Warn, wrong.clone(), "warn"),
+            (Warn, matching.clone(), "verified"),
+            (Ignore, missing, "unchecked"),
+            (Ignore, wrong, "unchecked"),
+            (Ignore, matching, "unchecked"),
+        ];
+        for (policy, checksum, expected) in cases {
+            let verdict = match verdict(policy, jar, checksum.clone(), "url", "lib-1.0.jar") {
+                Verdict::Verified => "verified",
+                Verdict::Unchecked => "unchecked",
+                Verdict::Warn(_) => "warn",
+                Verdict::Reject(_) => "reject",
+            };
+            assert_eq!(verdict, expected, "{:?} with {:?}", policy, checksum);
+        }
+    }
 }
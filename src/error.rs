@@ -0,0 +1,199 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// The errors that jargo reports to the user, each with what is needed to fix it.
+/// Every kind has its own exit code, so that scripts and CI can tell them apart.
+#[derive(Debug, Error)]
+pub enum JargoError {
+    /// Jargo.toml can't be read or has an invalid value
+    #[error("manifest {}{}{}: {message}", file.display(), position.map(|(l, c)| format!(":{}:{}", l, c)).unwrap_or_default(), key.as_ref().map(|k| format!(" at {}", k)).unwrap_or_default())]
+    Manifest {
+        file: PathBuf,
+        /// the key with the invalid value, like package.group
+        key: Option<String>,
+        /// the line and column
+        position: Option<(usize, usize)>,
+        message: String,
+    },
+    /// a dependency that isn't in any repository, or that has an invalid pom
+    #[error(
+        "could not resolve {coordinates}: {message}{}",
+        required_by(dependency_path)
+    )]
+    Resolution {
+        /// group:name:version
+        coordinates: String,
+        /// the dependencies that lead to it, nearest first and the project last
+        dependency_path: Vec<String>,
+        message: String,
+    },
+    /// a repository can't be reached, or the download stopped halfway
+    #[error("could not download {url}: {message}")]
+    Network { url: String, message: String },
+    /// a download doesn't match its checksum
+    #[error("{algorithm} checksum mismatch for {url}: expected {expected}, was {actual}")]
+    Checksum {
+        url: String,
+        /// like sha256, the extension of the checksum file
+        algorithm: String,
+        /// the downloaded file in the local cache
+        file: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    /// the repository has no checksum for a download
+    #[error("no checksum found for {url}")]
+    MissingChecksum { url: String },
+    /// a download without a valid PGP signature from a key in the keyring
+    #[error("could not verify the signature of {url}: {message}")]
+    Signature { url: String, message: String },
+    /// a group is signed with another key than the one in Jargo.lock
+    #[error("{group} is signed with key {fingerprint}, but was signed with {} before", known.join(", "))]
+    KeyChanged {
+        group: String,
+        fingerprint: String,
+        /// the fingerprints in Jargo.lock
+        known: Vec<String>,
+    },
+    /// javac reported errors
+    #[error("could not compile {project} ({sources}) due to {message}")]
+    Compile {
+        project: String,
+        /// the source directory, like src/test/java
+        sources: String,
+        message: String,
+    },
+    /// tests failed
+    #[error("{failed} tests failed")]
+    Test {
+        failed: usize,
+        /// the directories with the JUnit XML reports of the projects with failed tests
+        reports: Vec<PathBuf>,
+    },
+}
+
+fn required_by(dependency_path: &[String]) -> String {
+    if dependency_path.is_empty() {
+        String::new()
+    } else {
+        format!(", required by {}", dependency_path.join(" <- "))
+    }
+}
+
+impl JargoError {
+    /// what to do about it
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            JargoError::Manifest {
+                key: Some(key),
+                message,
+                ..
+            } if key.starts_with("package") && message.contains("missing") => Some(
+                "[package] needs a group, name and version, like group = \"com.example\"".into(),
+            ),
+            JargoError::Manifest { file, .. } => Some(format!("fix {} and try again", file.display())),
+            JargoError::Resolution { .. } => Some(
+                "check the group, name and version, or add the repository that has it to [repositories]"
+                    .into(),
+            ),
+            JargoError::Network { .. } => Some(
+                "check the network connection and the url of the repository, or try again later"
+                    .into(),
+            ),
+            JargoError::Checksum { file, .. } => Some(format!(
+                "the download may be corrupt or tampered with, {} was not kept, try again",
+                file.display()
+            )),
+            JargoError::MissingChecksum { .. } => Some(
+                "the repository has no .sha512, .sha256, .sha1 or .md5 file for it, set checksums = \"warn\" in [resolver] or for the repository to use it anyway"
+                    .into(),
+            ),
+            JargoError::Signature { .. } => Some(
+                "add the key of the publisher to the keyring in ~/jargo/config.toml, or set policy = \"warn\" in [signatures] there"
+                    .into(),
+            ),
+            JargoError::KeyChanged { group, .. } => Some(format!(
+                "make sure the new key belongs to the publisher of {}, then remove its signing-key from Jargo.lock",
+                group
+            )),
+            JargoError::Compile { .. } => None,
+            JargoError::Test { reports, .. } => Some(format!(
+                "the reports are in {}, `jargo test --failed` runs only the failed tests again",
+                reports
+                    .iter()
+                    .map(|r| r.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// 1 is left for the errors that aren't a JargoError
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            JargoError::Manifest { .. } => 2,
+            JargoError::Resolution { .. } => 3,
+            JargoError::Network { .. } => 4,
+            JargoError::Checksum { .. } | JargoError::MissingChecksum { .. } => 5,
+            JargoError::Compile { .. } => 6,
+            JargoError::Test { .. } => 7,
+            JargoError::Signature { .. } | JargoError::KeyChanged { .. } => 8,
+        }
+    }
+}
+
+/// the JargoError in the chain of an error, when there is one
+pub fn find(error: &anyhow::Error) -> Option<&JargoError> {
+    error.chain().find_map(|e| e.downcast_ref::<JargoError>())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::JargoError;
+
+    #[test]
+    fn message_and_exit_code() {
+        let error = JargoError::Resolution {
+            coordinates: "org.example:b:1.0".into(),
+            dependency_path: vec!["org.example:a:1.0".into(), "my.group:app:0.1".into()],
+            message: "not found in any repository".into(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "could not resolve org.example:b:1.0: not found in any repository, required by org.example:a:1.0 <- my.group:app:0.1"
+        );
+        assert_eq!(error.exit_code(), 3);
+
+        let error = JargoError::Manifest {
+            file: "Jargo.toml".into(),
+            key: Some("package.group".into()),
+            position: Some((3, 9)),
+            message: "missing".into(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "manifest Jargo.toml:3:9 at package.group: missing"
+        );
+    }
+
+    #[test]
+    fn package_hint_only_for_missing_keys() {
+        let error = |key: &str, message: &str| JargoError::Manifest {
+            file: "Jargo.toml".into(),
+            key: Some(key.into()),
+            position: None,
+            message: message.into(),
+        };
+        assert!(error("package", "[package] is missing")
+            .hint()
+            .is_some_and(|h| h.starts_with("[package] needs")));
+        assert!(error("package.version", "is missing")
+            .hint()
+            .is_some_and(|h| h.starts_with("[package] needs")));
+        assert_eq!(
+            error("package.kind", "should be \"jar\" or \"uber\", not \"war\"").hint(),
+            Some("fix Jargo.toml and try again".into())
+        );
+    }
+}
@@ -1,5 +1,19 @@
+pub mod cache;
+pub mod checksum;
+pub mod classfile;
 pub mod compile;
 pub mod config;
 pub mod deploader;
+pub mod error;
+pub mod lockfile;
+pub mod manifest;
 pub mod maven;
+pub mod package;
 pub mod project;
+pub mod run;
+pub mod signature;
+pub mod test;
+pub mod toolchain;
+pub mod version;
+pub mod watch;
+pub mod workspace;
@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Error};
+use toml::{Table, Value};
+
+use crate::project::Artifact;
+
+/// Jargo.lock, in the root of the workspace: the artifacts that all its projects were built with.
+///
+/// ```toml
+/// [[artifact]]
+/// group = "org.slf4j"
+/// name = "slf4j-api"
+/// version = "2.0.9"
+///
+/// [[signing-key]]
+/// group = "org.slf4j"
+/// fingerprints = ["475F3B8E59E6E63AA78067482C7B12F2A511E325"]
+/// ```
+///
+/// The signing keys are trusted on first use: a group that is signed with another key later
+/// is reported, see [Lockfile::trust].
+#[derive(Debug, Default, PartialEq)]
+pub struct Lockfile {
+    /// group, name and version
+    pub artifacts: BTreeSet<(String, String, String)>,
+    /// the fingerprints of the PGP keys that signed the artifacts of a group
+    pub signing_keys: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Lockfile {
+    /// the artifacts, with the signers as group and fingerprint
+    pub fn new<'a>(
+        artifacts: impl Iterator<Item = &'a Artifact>,
+        signers: impl Iterator<Item = &'a (String, String)>,
+    ) -> Self {
+        let mut signing_keys: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (group, fingerprint) in signers {
+            signing_keys
+                .entry(group.clone())
+                .or_default()
+                .insert(fingerprint.clone());
+        }
+        Self {
+            artifacts: artifacts
+                .map(|a| (a.group.clone(), a.name.clone(), a.version.to_string()))
+                .collect(),
+            signing_keys,
+        }
+    }
+
+    /// Compares the signing keys with the ones in the previous lockfile, and keeps the previous
+    /// ones of the groups that are still used. Returns the new keys of groups that were signed
+    /// with other keys before, with those other keys. Those new keys are not recorded, so they
+    /// are reported by every build until the entry of the group is removed from Jargo.lock.
+    pub fn trust(&mut self, previous: &Lockfile) -> Vec<(String, String, Vec<String>)> {
+        let mut changed = vec![];
+        for (group, fingerprints) in &mut self.signing_keys {
+            if let Some(known) = previous.signing_keys.get(group) {
+                for fingerprint in fingerprints.difference(known) {
+                    changed.push((
+                        group.clone(),
+                        fingerprint.clone(),
+                        known.iter().cloned().collect(),
+                    ));
+                }
+                *fingerprints = known.clone();
+            }
+        }
+        let groups: BTreeSet<&String> = self.artifacts.iter().map(|(g, _, _)| g).collect();
+        for (group, known) in &previous.signing_keys {
+            if groups.contains(group) {
+                self.signing_keys
+                    .entry(group.clone())
+                    .or_default()
+                    .extend(known.iter().cloned());
+            }
+        }
+        changed
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let table = fs::read_to_string(path)?.parse::<Table>()?;
+        let mut lockfile = Self::default();
+        let invalid = || anyhow!("{} is not a valid lockfile", path.display());
+        if let Some(artifacts) = table.get("artifact") {
+            for artifact in artifacts.as_array().ok_or_else(invalid)? {
+                let field = |key: &str| -> Result<String, Error> {
+                    Ok(artifact
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .ok_or_else(invalid)?
+                        .to_owned())
+                };
+                lockfile
+                    .artifacts
+                    .insert((field("group")?, field("name")?, field("version")?));
+            }
+        }
+        if let Some(keys) = table.get("signing-key") {
+            for key in keys.as_array().ok_or_else(invalid)? {
+                let group = key
+                    .get("group")
+                    .and_then(Value::as_str)
+                    .ok_or_else(invalid)?;
+                let fingerprints = key
+                    .get("fingerprints")
+                    .and_then(Value::as_array)
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(|f| f.as_str().map(String::from).ok_or_else(invalid))
+                    .collect::<Result<BTreeSet<_>, _>>()?;
+                lockfile.signing_keys.insert(group.into(), fingerprints);
+            }
+        }
+        Ok(lockfile)
+    }
+
+    /// writes the file only when it changes, so that its modification time means something
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let mut contents = String::from("# written by jargo, do not edit\n");
+        for (group, name, version) in &self.artifacts {
+            contents.push_str(&format!(
+                "\n[[artifact]]\ngroup = {}\nname = {}\nversion = {}\n",
+                Value::from(group.as_str()),
+                Value::from(name.as_str()),
+                Value::from(version.as_str())
+            ));
+        }
+        for (group, fingerprints) in &self.signing_keys {
+            contents.push_str(&format!(
+                "\n[[signing-key]]\ngroup = {}\nfingerprints = {}\n",
+                Value::from(group.as_str()),
+                Value::from(fingerprints.iter().map(String::as_str).collect::<Vec<_>>())
+            ));
+        }
+        if fs::read_to_string(path).ok().as_ref() != Some(&contents) {
+            // a build that is interrupted doesn't lose the recorded keys
+            crate::cache::write_atomic(path, contents.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lockfile::Lockfile;
+    use crate::project::Artifact;
+
+    #[test]
+    fn write_and_read() {
+        let artifacts = [
+            Artifact::new("org.slf4j", "slf4j-api", "2.0.9"),
+            Artifact::new("junit", "junit", "4.13.2"),
+            Artifact::new("org.slf4j", "slf4j-api", "2.0.9"),
+        ];
+        let signers = [("junit".to_owned(), "0123ABCD".to_owned())];
+        let lockfile = Lockfile::new(artifacts.iter(), signers.iter());
+        assert_eq!(lockfile.artifacts.len(), 2);
+
+        let path = std::env::temp_dir().join(format!("jargo-lock-{}", std::process::id()));
+        lockfile.write(&path).unwrap();
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("[[artifact]]\ngroup = \"junit\"\nname = \"junit\"\nversion = \"4.13.2\"\n"));
+        assert_eq!(Lockfile::read(&path).unwrap(), lockfile);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn trust_on_first_use() {
+        let artifacts = [
+            Artifact::new("org.slf4j", "slf4j-api", "2.0.9"),
+            Artifact::new("junit", "junit", "4.13.2"),
+        ];
+        let previous = Lockfile::new(
+            artifacts.iter(),
+            [
+                ("junit".to_owned(), "AAAA".to_owned()),
+                ("org.slf4j".to_owned(), "BBBB".to_owned()),
+            ]
+            .iter(),
+        );
+        // without a signer for slf4j, like when signatures are not checked anymore
+        let mut lockfile = Lockfile::new(
+            artifacts.iter(),
+            [("junit".to_owned(), "CCCC".to_owned())].iter(),
+        );
+        assert_eq!(
+            lockfile.trust(&previous),
+            vec![("junit".into(), "CCCC".into(), vec!["AAAA".into()])]
+        );
+        assert_eq!(lockfile.signing_keys["junit"].len(), 1);
+        assert!(lockfile.signing_keys["junit"].contains("AAAA"));
+        assert!(lockfile.signing_keys["org.slf4j"].contains("BBBB"));
+    }
+
+    #[test]
+    fn changed_key_reported_until_accepted() {
+        let artifacts = [Artifact::new("junit", "junit", "4.13.2")];
+        let signed_by = |fingerprint: &str| {
+            Lockfile::new(
+                artifacts.iter(),
+                [("junit".to_owned(), fingerprint.to_owned())].iter(),
+            )
+        };
+        let mut previous = signed_by("AAAA");
+        // with key-changes = "warn" the build goes on and writes the lockfile, every time
+        for _ in 0..2 {
+            let mut lockfile = signed_by("CCCC");
+            assert_eq!(lockfile.trust(&previous).len(), 1);
+            previous = lockfile;
+        }
+        // the old entry is removed to accept the new key
+        previous.signing_keys.clear();
+        let mut lockfile = signed_by("CCCC");
+        assert!(lockfile.trust(&previous).is_empty());
+        assert!(lockfile.signing_keys["junit"].contains("CCCC"));
+    }
+}
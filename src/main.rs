@@ -1,16 +1,265 @@
+use std::path::Path;
+
 use anyhow::Error;
+use clap::{Parser, Subcommand};
+use colored::Colorize;
 
-use jargo::config::config;
+use jargo::compile::MessageFormat;
+use jargo::error::JargoError;
+use jargo::project::{Forks, PackageKind};
+use jargo::test::{TestFilter, TEST_REPORTS};
+use jargo::watch::WatchAction;
+use jargo::workspace::load_workspace;
 
-/// sample main that will be replaced by a generic one later
-fn main() -> anyhow::Result<(), Error> {
-    let repo  = format!("{}/jargo/repo",config().user_home);
-    std::fs::remove_dir_all(&repo)?;
-    std::fs::create_dir(repo)?;
+/// An experimental build tool for Java
+#[derive(Parser)]
+#[command(name = "jargo")]
+struct Cli {
+    /// path to the Jargo.toml of the project
+    #[arg(long, global = true)]
+    manifest_path: Option<String>,
+    /// how to show the compiler errors and warnings
+    #[arg(long, global = true, value_enum, default_value_t)]
+    message_format: MessageFormat,
+    /// check all repositories for new snapshots now, whatever their update policy
+    #[arg(long, global = true)]
+    refresh_snapshots: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-    let project = jargo::project::load_project(Some("tests/sample_project/Jargo.toml"))?;
-    println!("{:?}", project);
-    jargo::deploader::load(&project)?;
-    jargo::compile::run(&project)?;
+#[derive(Subcommand)]
+enum Command {
+    /// show the installed JDKs
+    Toolchain {
+        #[command(subcommand)]
+        command: ToolchainCommand,
+    },
+    /// resolve the dependencies and compile the sources
+    Build,
+    /// show or clean up the downloaded dependencies in ~/jargo/repo
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// show the problems in Jargo.toml, and in those of the members of a workspace
+    CheckManifest,
+    /// build and create a jar in target/
+    Package {
+        /// also add all runtime dependencies to the jar
+        #[arg(long)]
+        fat: bool,
+    },
+    /// build and run the main class
+    Run {
+        /// the main class, instead of main-class in [package] or the only class with a main method
+        #[arg(long)]
+        main: Option<String>,
+        /// the project in the workspace to run
+        #[arg(long)]
+        member: Option<String>,
+        /// the arguments for the program, after --
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// build, test or run again every time something changes in src or Jargo.toml
+    Watch {
+        #[arg(value_enum, default_value_t)]
+        action: WatchAction,
+        /// the project in the workspace to run
+        #[arg(long)]
+        member: Option<String>,
+        /// the arguments for the program with `run`, after --
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// build and run the tests
+    Test {
+        /// glob for the test classes to run, optionally followed by #glob for the methods
+        pattern: Option<String>,
+        /// only run tests with this JUnit 5 tag, JUnit 4 category or TestNG group
+        #[arg(long)]
+        tag: Vec<String>,
+        /// don't run tests with this JUnit 5 tag, JUnit 4 category or TestNG group
+        #[arg(long)]
+        exclude_tag: Vec<String>,
+        /// only run the tests that failed in the previous run
+        #[arg(long)]
+        failed: bool,
+        /// also write summary.json in target/test-reports
+        #[arg(long)]
+        json_summary: bool,
+        /// the number of JVMs to run the test classes in, eg. 4 or 1C for one per CPU core
+        #[arg(short, long)]
+        jobs: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolchainCommand {
+    /// list the JDKs found in JAVA_HOME, /usr/lib/jvm, ~/.sdkman/candidates/java and ~/.jdks
+    List,
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// list the cached artifacts, with their size and when they were last used
+    List,
+    /// show the number of cached artifacts and the space they take
+    Size,
+    /// check every cached file with its checksum, and remove the ones that don't match
+    Verify,
+    /// remove the artifacts that were not used in a while, and that are not in a recently used Jargo.lock
+    Prune {
+        /// the number of days
+        #[arg(long, default_value_t = 30)]
+        days: u64,
+    },
+    /// remove all artifacts, or those of a group, group:name or group:name:version
+    Clean { artifact: Option<String> },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli) {
+        std::process::exit(report(&e));
+    }
+}
+
+/// Shows the error with its causes and what to do about it, and returns the exit code
+fn report(error: &Error) -> i32 {
+    eprintln!("{}: {}", "error".red().bold(), error);
+    for cause in error.chain().skip(1) {
+        eprintln!("  {} {}", "caused by:".bold(), cause);
+    }
+    let jargo_error = jargo::error::find(error);
+    if let Some(hint) = jargo_error.and_then(JargoError::hint) {
+        eprintln!("  {} {}", "hint:".cyan().bold(), hint);
+    }
+    jargo_error.map_or(1, JargoError::exit_code)
+}
+
+fn run(cli: Cli) -> Result<(), Error> {
+    jargo::config::init()?;
+    // doesn't need a project
+    if let Some(Command::Toolchain {
+        command: ToolchainCommand::List,
+    }) = cli.command
+    {
+        list_toolchains();
+        return Ok(());
+    }
+    if let Some(Command::Cache { command }) = &cli.command {
+        return match command {
+            CacheCommand::List => jargo::cache::list(),
+            CacheCommand::Size => jargo::cache::size(),
+            CacheCommand::Verify => jargo::cache::verify(),
+            CacheCommand::Prune { days } => jargo::cache::prune(*days),
+            CacheCommand::Clean { artifact } => jargo::cache::clean(artifact.as_deref()),
+        };
+    }
+    if let Some(Command::CheckManifest) = cli.command {
+        return jargo::manifest::check_manifest(cli.manifest_path.as_deref(), cli.message_format);
+    }
+
+    if let Some(Command::Watch {
+        action,
+        member,
+        args,
+    }) = &cli.command
+    {
+        return jargo::watch::watch(
+            cli.manifest_path.as_deref(),
+            *action,
+            member.as_deref(),
+            cli.message_format,
+            cli.refresh_snapshots,
+            args,
+        );
+    }
+
+    let mut workspace = load_workspace(cli.manifest_path.as_deref())?;
+    if cli.refresh_snapshots {
+        workspace.refresh_snapshots();
+    }
+    let mut builds = workspace.build(cli.message_format)?;
+
+    match cli.command {
+        Some(Command::Package { fat }) => {
+            for (i, project) in workspace.selected() {
+                let kind = if fat { PackageKind::Uber } else { project.kind };
+                jargo::package::run(project, &builds[i].classpath, kind)?;
+            }
+        }
+        Some(Command::Test {
+            pattern,
+            tag,
+            exclude_tag,
+            failed,
+            json_summary,
+            jobs,
+        }) => {
+            if let Some(jobs) = jobs {
+                let forks = Forks::parse(&jobs)?;
+                for project in &mut workspace.projects {
+                    project.test.forks = forks;
+                }
+            }
+            let filter = TestFilter {
+                pattern,
+                tags: tag,
+                exclude_tags: exclude_tag,
+                failed,
+            };
+            let (mut failed, mut reports) = (0, vec![]);
+            for (i, project) in workspace.selected() {
+                let build = &mut builds[i];
+                jargo::compile::run_tests(project, &build.classpath, &mut build.diagnostics)?;
+                let summary = jargo::test::run(project, &build.classpath, &filter)?;
+                if json_summary {
+                    jargo::test::write_json_summary(Path::new(&project.target_dir), &summary)?;
+                }
+                if !summary.is_success() {
+                    failed += summary.failed().len();
+                    reports.push(Path::new(&project.target_dir).join(TEST_REPORTS));
+                }
+            }
+            if failed > 0 {
+                return Err(JargoError::Test { failed, reports }.into());
+            }
+        }
+        Some(Command::Run { main, member, args }) => {
+            let i = workspace.runnable(member.as_deref())?;
+            let code = jargo::run::run(
+                &workspace.projects[i],
+                &builds[i].classpath,
+                main.as_deref(),
+                &args,
+            )?;
+            std::process::exit(code);
+        }
+        Some(Command::Build)
+        | Some(Command::Cache { .. })
+        | Some(Command::CheckManifest)
+        | Some(Command::Toolchain { .. })
+        | Some(Command::Watch { .. })
+        | None => {}
+    }
     Ok(())
 }
+
+fn list_toolchains() {
+    let jdks = jargo::toolchain::discover();
+    if jdks.is_empty() {
+        println!("{}", "No JDKs found".yellow());
+    }
+    for jdk in jdks {
+        println!(
+            "{:<4} {:<12} {:<20} {}",
+            jdk.feature(),
+            jdk.version,
+            jdk.vendor.unwrap_or_default(),
+            jdk.home.display()
+        );
+    }
+}
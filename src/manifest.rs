@@ -0,0 +1,755 @@
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use anyhow::Error;
+use colored::Colorize;
+use regex::Regex;
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_spanned::Spanned;
+use toml::value::Datetime;
+use toml::Table;
+
+use crate::compile::diagnostics::{Diagnostic, Severity};
+use crate::compile::{Diagnostics, MessageFormat};
+use crate::error::JargoError;
+use crate::project::UpdatePolicy;
+use crate::version::VersionRange;
+use crate::workspace::WorkspaceConfig;
+
+/// Something wrong in a Jargo.toml, with the position of the key or value in the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// the dotted key, like compile.release
+    pub key: String,
+    pub message: String,
+    /// the bytes in the file
+    pub span: Option<Range<usize>>,
+}
+
+/// Reads a Jargo.toml and checks it against the schema.
+/// The first problem is returned as a [JargoError::Manifest], with its line and column.
+pub fn read(jargo_file: &Path) -> Result<Table, JargoError> {
+    let manifest_error = |key: Option<String>, position, message: String| JargoError::Manifest {
+        file: jargo_file.to_path_buf(),
+        key,
+        position,
+        message,
+    };
+    let contents =
+        fs::read_to_string(jargo_file).map_err(|e| manifest_error(None, None, e.to_string()))?;
+    let problems = check(&contents);
+    if let Some(problem) = problems.first() {
+        let mut message = problem.message.clone();
+        if problems.len() > 1 {
+            message.push_str(&format!(
+                " (and {} more, see `jargo check-manifest`)",
+                problems.len() - 1
+            ));
+        }
+        let position = problem
+            .span
+            .as_ref()
+            .map(|span| line_and_column(&contents, span.start));
+        return Err(manifest_error(
+            Some(problem.key.clone()).filter(|k| !k.is_empty()),
+            position,
+            message,
+        ));
+    }
+    contents
+        .parse::<Table>()
+        .map_err(|e| manifest_error(None, None, e.to_string().trim_end().into()))
+}
+
+/// `jargo check-manifest`: shows all problems in the Jargo.toml, and in those of the members
+/// when it is the root of a workspace
+pub fn check_manifest(
+    jargo_file: Option<&str>,
+    message_format: MessageFormat,
+) -> Result<(), Error> {
+    let jargo_file = PathBuf::from(jargo_file.unwrap_or("./Jargo.toml"));
+    let mut manifests = vec![jargo_file.clone()];
+    if read(&jargo_file).is_ok() {
+        if let Some(workspace) = WorkspaceConfig::read(&jargo_file)? {
+            manifests.extend(workspace.members.iter().map(|m| m.join("Jargo.toml")));
+        }
+    }
+
+    let diagnostics = Diagnostics::new(message_format);
+    let mut count = 0;
+    for manifest in &manifests {
+        let contents = fs::read_to_string(manifest).map_err(|e| JargoError::Manifest {
+            file: manifest.clone(),
+            key: None,
+            position: None,
+            message: e.to_string(),
+        })?;
+        let root = manifest.parent().unwrap_or(Path::new(""));
+        let problems = check(&contents);
+        for problem in &problems {
+            diagnostics.show(&to_diagnostic(problem, manifest, &contents), root);
+        }
+        count += problems.len();
+        if problems.is_empty() && message_format == MessageFormat::Human {
+            println!("{} {}", "Checked".green(), manifest.display());
+        }
+    }
+    match count {
+        0 => Ok(()),
+        count => Err(JargoError::Manifest {
+            file: jargo_file,
+            key: None,
+            position: None,
+            message: format!(
+                "found {} problem{}",
+                count,
+                if count == 1 { "" } else { "s" }
+            ),
+        }
+        .into()),
+    }
+}
+
+/// all problems in the contents of a Jargo.toml, in the order of the file
+pub fn check(contents: &str) -> Vec<Problem> {
+    let root = match toml::from_str::<Node>(contents) {
+        Ok(root) => root,
+        Err(e) => {
+            return vec![Problem {
+                key: String::new(),
+                message: e.message().trim_end().replace('\n', ", "),
+                span: e.span(),
+            }]
+        }
+    };
+    let Node::Table(entries) = &root else {
+        return vec![];
+    };
+    let mut problems = vec![];
+    check_table(entries, ROOT, "", None, &mut problems);
+    if !entries
+        .iter()
+        .any(|(k, _)| k.get_ref() == "package" || k.get_ref() == "workspace")
+    {
+        problems.push(Problem {
+            key: "package".into(),
+            message: "[package] is missing, it needs a group, name and version".into(),
+            span: None,
+        });
+    }
+    problems.sort_by_key(|p| p.span.as_ref().map_or(0, |s| s.start));
+    problems
+}
+
+/// what a value should look like
+#[derive(Debug, Clone, Copy)]
+enum Shape {
+    String,
+    Integer,
+    Boolean,
+    /// a string, number or boolean
+    Scalar,
+    Strings,
+    OneOf(&'static [&'static str]),
+    Either(&'static [Shape]),
+    Table(&'static [Key]),
+    /// [package], that can have other strings, for the placeholders in the resources
+    Package,
+    /// a table with any keys, and values of one shape
+    Map(&'static Shape),
+    Dependencies,
+    Version,
+    /// a version or a version range, for dependencies
+    Requirement,
+    /// "always", "daily", "interval:N" or "never"
+    UpdatePolicy,
+}
+
+impl Shape {
+    fn describe(&self) -> String {
+        match self {
+            Shape::String => "a string".into(),
+            Shape::Integer => "a number".into(),
+            Shape::Boolean => "true or false".into(),
+            Shape::Scalar => "a string, number or boolean".into(),
+            Shape::Strings => "a list of strings".into(),
+            Shape::OneOf(options) => format!(
+                "one of {}",
+                options
+                    .iter()
+                    .map(|o| format!("\"{}\"", o))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Shape::Either(shapes) => {
+                let mut shapes: Vec<String> = shapes.iter().map(Shape::describe).collect();
+                let last = shapes.pop().unwrap_or_default();
+                if shapes.is_empty() {
+                    last
+                } else {
+                    format!("{} or {}", shapes.join(", "), last)
+                }
+            }
+            Shape::Table(_) | Shape::Package | Shape::Map(_) | Shape::Dependencies => {
+                "a table".into()
+            }
+            Shape::Version => "a version, like \"1.0\"".into(),
+            Shape::Requirement => {
+                "a version, like \"1.0\", or a range, like \"[1.0,2.0)\" or \"^1.2\"".into()
+            }
+            Shape::UpdatePolicy => {
+                "\"always\", \"daily\", \"interval:N\" (minutes) or \"never\"".into()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Key {
+    name: &'static str,
+    shape: Shape,
+    required: bool,
+}
+
+const fn key(name: &'static str, shape: Shape) -> Key {
+    Key {
+        name,
+        shape,
+        required: false,
+    }
+}
+
+const fn required(name: &'static str, shape: Shape) -> Key {
+    Key {
+        name,
+        shape,
+        required: true,
+    }
+}
+
+const STRING_OR_INTEGER: Shape = Shape::Either(&[Shape::String, Shape::Integer]);
+
+const ROOT: &[Key] = &[
+    key("package", Shape::Package),
+    key("repositories", Shape::Map(&Shape::Table(REPOSITORY))),
+    key("resolver", Shape::Table(RESOLVER)),
+    key("dependencies", Shape::Dependencies),
+    key("test-dependencies", Shape::Dependencies),
+    key("annotation-processors", Shape::Dependencies),
+    key("shade", Shape::Table(SHADE)),
+    key("resources", Shape::Table(RESOURCES)),
+    key("compile", Shape::Table(COMPILE)),
+    key("toolchain", Shape::Table(TOOLCHAIN)),
+    key("test", Shape::Table(TEST)),
+    key("run", Shape::Table(RUN)),
+    key("workspace", Shape::Table(WORKSPACE)),
+];
+
+const PACKAGE: &[Key] = &[
+    required("group", Shape::String),
+    required("name", Shape::String),
+    required("version", Shape::Version),
+    key("kind", Shape::OneOf(&["jar", "uber", "fat"])),
+    key("main-class", Shape::String),
+    key("java", STRING_OR_INTEGER),
+];
+
+const CHECKSUM_POLICY: Shape = Shape::OneOf(&["fail", "warn", "ignore"]);
+
+const REPOSITORY: &[Key] = &[
+    required("url", Shape::String),
+    key("checksums", CHECKSUM_POLICY),
+    key("snapshots", Shape::UpdatePolicy),
+    key("releases", Shape::UpdatePolicy),
+];
+
+const RESOLVER: &[Key] = &[
+    key("checksums", CHECKSUM_POLICY),
+    key("snapshots", Shape::UpdatePolicy),
+    key("releases", Shape::UpdatePolicy),
+];
+
+const SHADE: &[Key] = &[key("relocate", Shape::Map(&Shape::String))];
+
+const RESOURCES: &[Key] = &[
+    key("filter", Shape::Strings),
+    key("properties", Shape::Map(&Shape::Scalar)),
+];
+
+const COMPILE: &[Key] = &[
+    key("release", STRING_OR_INTEGER),
+    key("encoding", Shape::String),
+    key("parameters", Shape::Boolean),
+    key(
+        "lint",
+        Shape::Either(&[Shape::Boolean, Shape::String, Shape::Strings]),
+    ),
+    key("warnings-as-errors", Shape::Boolean),
+    key("debug", Shape::Either(&[Shape::Boolean, Shape::String])),
+    key("processor-options", Shape::Map(&Shape::Scalar)),
+    key("args", Shape::Strings),
+];
+
+const TOOLCHAIN: &[Key] = &[key("java", STRING_OR_INTEGER), key("vendor", Shape::String)];
+
+const TEST: &[Key] = &[
+    key("forks", STRING_OR_INTEGER),
+    key("timeout", Shape::Integer),
+];
+
+const RUN: &[Key] = &[
+    key("jvm-args", Shape::Strings),
+    key("env", Shape::Map(&Shape::Scalar)),
+];
+
+const WORKSPACE: &[Key] = &[
+    key("members", Shape::Strings),
+    key("dependencies", Shape::Dependencies),
+];
+
+const DEPENDENCY: &[Key] = &[
+    key("version", Shape::Requirement),
+    key("path", Shape::String),
+    key("workspace", Shape::Boolean),
+];
+
+type Entries = Vec<(Spanned<String>, Spanned<Node>)>;
+
+/// Checks the keys of a table against the schema. `span` is where the table is,
+/// for the keys that are missing.
+fn check_table(
+    entries: &Entries,
+    keys: &[Key],
+    path: &str,
+    span: Option<Range<usize>>,
+    problems: &mut Vec<Problem>,
+) {
+    let lenient = path == "package";
+    for (name, value) in entries {
+        let full_name = join(path, name.get_ref());
+        match keys.iter().find(|k| k.name == name.get_ref().as_str()) {
+            Some(key) => check_value(value, &key.shape, &full_name, problems),
+            // [compile.test] has the same keys as [compile]
+            None if path == "compile" && name.get_ref() == "test" => {
+                check_value(value, &Shape::Table(COMPILE), &full_name, problems)
+            }
+            None => {
+                let suggestion = suggest(name.get_ref(), keys.iter().map(|k| k.name));
+                // other strings in [package] are placeholders for the resources
+                if lenient && suggestion.is_none() {
+                    if !matches!(value.get_ref(), Node::String(_)) {
+                        problems.push(mismatch(value, &Shape::String, &full_name));
+                    }
+                    continue;
+                }
+                let is_section = path.is_empty() && matches!(value.get_ref(), Node::Table(_));
+                let (what, full_name, suggestion) = if is_section {
+                    (
+                        "section",
+                        format!("[{}]", full_name),
+                        suggestion.map(|s| format!("[{}]", s)),
+                    )
+                } else {
+                    ("key", full_name, suggestion.map(String::from))
+                };
+                let mut message = format!("unknown {} {}", what, full_name);
+                if let Some(suggestion) = suggestion {
+                    message.push_str(&format!(", did you mean {}?", suggestion));
+                }
+                problems.push(Problem {
+                    key: join(path, name.get_ref()),
+                    message,
+                    span: Some(name.span()),
+                });
+            }
+        }
+    }
+    for key in keys.iter().filter(|k| k.required) {
+        if !entries.iter().any(|(name, _)| name.get_ref() == key.name) {
+            problems.push(Problem {
+                key: join(path, key.name),
+                message: format!("{} is missing", join(path, key.name)),
+                span: span.clone(),
+            });
+        }
+    }
+}
+
+fn check_value(value: &Spanned<Node>, shape: &Shape, key: &str, problems: &mut Vec<Problem>) {
+    let matches = match (shape, value.get_ref()) {
+        (Shape::String, Node::String(_)) => true,
+        (Shape::Integer, Node::Integer) => true,
+        (Shape::Boolean, Node::Boolean) => true,
+        (Shape::Scalar, node) => !matches!(node, Node::Table(_) | Node::Array(_)),
+        (Shape::Strings, Node::Array(items)) => {
+            items.iter().all(|i| matches!(i.get_ref(), Node::String(_)))
+        }
+        (Shape::OneOf(options), Node::String(value)) => options.contains(&value.as_str()),
+        (Shape::UpdatePolicy, Node::String(value)) => UpdatePolicy::parse(value).is_ok(),
+        (Shape::Either(shapes), _) => {
+            let fits = shapes.iter().any(|shape| {
+                let mut inner = vec![];
+                check_value(value, shape, key, &mut inner);
+                inner.is_empty()
+            });
+            fits
+        }
+        (Shape::Table(keys), Node::Table(entries)) => {
+            check_table(entries, keys, key, Some(value.span()), problems);
+            true
+        }
+        (Shape::Package, Node::Table(entries)) => {
+            check_table(entries, PACKAGE, key, Some(value.span()), problems);
+            true
+        }
+        (Shape::Map(shape), Node::Table(entries)) => {
+            for (name, value) in entries {
+                check_value(value, shape, &join(key, name.get_ref()), problems);
+            }
+            true
+        }
+        (Shape::Dependencies, Node::Table(entries)) => {
+            for (name, value) in entries {
+                check_dependency(name, value, key, problems);
+            }
+            true
+        }
+        (Shape::Version, Node::String(version)) => {
+            if !is_version(version) {
+                problems.push(Problem {
+                    key: key.into(),
+                    message: format!("{} has an invalid version \"{}\"", key, version),
+                    span: Some(value.span()),
+                });
+            }
+            true
+        }
+        (Shape::Requirement, Node::String(version)) if VersionRange::is_range(version) => {
+            if let Err(e) = VersionRange::parse(version) {
+                problems.push(Problem {
+                    key: key.into(),
+                    message: format!("{} has an {}", key, e),
+                    span: Some(value.span()),
+                });
+            }
+            true
+        }
+        (Shape::Requirement, Node::String(_)) => {
+            check_value(value, &Shape::Version, key, problems);
+            true
+        }
+        _ => false,
+    };
+    if !matches {
+        problems.push(mismatch(value, shape, key));
+    }
+}
+
+/// a dependency is "group:name" = "version", or a table with the version, a path or workspace = true
+fn check_dependency(
+    name: &Spanned<String>,
+    value: &Spanned<Node>,
+    table: &str,
+    problems: &mut Vec<Problem>,
+) {
+    let key = join(table, name.get_ref());
+    if !is_coordinates(name.get_ref()) {
+        problems.push(Problem {
+            key: key.clone(),
+            message: format!(
+                "invalid coordinates \"{}\" in {}, use \"group:name\"",
+                name.get_ref(),
+                table
+            ),
+            span: Some(name.span()),
+        });
+    }
+    match value.get_ref() {
+        Node::String(_) => check_value(value, &Shape::Requirement, &key, problems),
+        Node::Table(entries) => {
+            check_table(entries, DEPENDENCY, &key, Some(value.span()), problems);
+            if !entries
+                .iter()
+                .any(|(k, _)| DEPENDENCY.iter().any(|d| d.name == k.get_ref().as_str()))
+            {
+                problems.push(Problem {
+                    key: key.clone(),
+                    message: format!("{} should have a version, a path or workspace = true", key),
+                    span: Some(value.span()),
+                });
+            }
+        }
+        _ => problems.push(Problem {
+            key: key.clone(),
+            message: format!(
+                "{} should be a version, like \"1.0\", or a table, like {{ version = \"1.0\" }}, not {}",
+                key,
+                value.get_ref()
+            ),
+            span: Some(value.span()),
+        }),
+    }
+}
+
+fn mismatch(value: &Spanned<Node>, shape: &Shape, key: &str) -> Problem {
+    let actual = match (shape, value.get_ref()) {
+        (Shape::OneOf(_) | Shape::UpdatePolicy, Node::String(value)) => format!("\"{}\"", value),
+        (_, value) => value.to_string(),
+    };
+    Problem {
+        key: key.into(),
+        message: format!("{} should be {}, not {}", key, shape.describe(), actual),
+        span: Some(value.span()),
+    }
+}
+
+/// group:name, with the characters that maven allows
+fn is_coordinates(name: &str) -> bool {
+    static PART: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_.-]+$").unwrap());
+    matches!(name.split_once(':'), Some((group, name)) if PART.is_match(group) && PART.is_match(name))
+}
+
+/// like 1.0, 2.0.9, 3.0.0-SNAPSHOT or 1.5.5.Final
+pub(crate) fn is_version(version: &str) -> bool {
+    static VERSION: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._+-]*$").unwrap());
+    VERSION.is_match(version)
+}
+
+/// The known key that was probably meant: the same with dashes instead of underscores or in
+/// another case, or else the one that is at most a few typos away
+fn suggest<'a>(name: &str, known: impl Iterator<Item = &'a str> + Clone) -> Option<&'a str> {
+    let normalize = |name: &str| name.to_lowercase().replace('_', "-");
+    if let Some(same) = known.clone().find(|k| normalize(k) == normalize(name)) {
+        return Some(same);
+    }
+    known
+        .map(|k| (strsim::damerau_levenshtein(name, k), k))
+        .filter(|(distance, k)| *distance <= (k.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, k)| k)
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.into()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// 1-based, the column in characters
+fn line_and_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn to_diagnostic(problem: &Problem, file: &Path, contents: &str) -> Diagnostic {
+    let position = problem
+        .span
+        .as_ref()
+        .map(|span| line_and_column(contents, span.start));
+    Diagnostic {
+        file: Some(file.to_path_buf()),
+        line: position.map(|(line, _)| line),
+        column: position.map(|(_, column)| column),
+        severity: Severity::Error,
+        message: problem.message.clone(),
+        snippet: position
+            .and_then(|(line, _)| contents.lines().nth(line - 1))
+            .map(String::from),
+    }
+}
+
+/// A TOML value that knows where its keys and values are in the file
+#[derive(Debug)]
+enum Node {
+    Table(Entries),
+    Array(Vec<Spanned<Node>>),
+    String(String),
+    Integer,
+    Float,
+    Boolean,
+    Datetime,
+}
+
+impl fmt::Display for Node {
+    /// what kind of value it is, for the messages
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Node::Table(_) => "a table",
+            Node::Array(_) => "a list",
+            Node::String(_) => "a string",
+            Node::Integer | Node::Float => "a number",
+            Node::Boolean => "a boolean",
+            Node::Datetime => "a date",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a TOML value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Node, E> {
+        Ok(Node::Boolean)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Node, E> {
+        Ok(Node::Integer)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Node, E> {
+        Ok(Node::Integer)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Node, E> {
+        Ok(Node::Float)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Node, E> {
+        Ok(Node::String(value.into()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Node::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut entries = vec![];
+        loop {
+            match map.next_key::<Spanned<String>>() {
+                Ok(Some(key)) => entries.push((key, map.next_value()?)),
+                Ok(None) => return Ok(Node::Table(entries)),
+                // toml passes a date as a map with one key without a position,
+                // and the date as a string
+                Err(e) if entries.is_empty() => {
+                    let date = map.next_value::<String>().ok();
+                    return match date.and_then(|d| d.parse::<Datetime>().ok()) {
+                        Some(_) => Ok(Node::Datetime),
+                        None => Err(e),
+                    };
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::manifest::{check, suggest};
+
+    #[test]
+    fn problems() {
+        let contents = r#"[package]
+group = "a"
+name = "b"
+version = "1.0 beta"
+mian-class = "a.Main"
+description = "placeholder"
+
+[test_dependencies]
+"junit:junit" = "4.13.2"
+
+[dependencies]
+"org.slf4j" = "2.0.9"
+"a:c" = { verison = "1.0" }
+"a:d" = "[1.0"
+"a:e" = "^1.2"
+
+[compile]
+release = [17]
+"#;
+        let problems: Vec<(String, usize)> = check(contents)
+            .into_iter()
+            .map(|p| (p.message, p.span.unwrap().start))
+            .collect();
+        let line = |offset: usize| contents[..offset].matches('\n').count() + 1;
+        let problems: Vec<(&str, usize)> = problems
+            .iter()
+            .map(|(m, o)| (m.as_str(), line(*o)))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                ("package.version has an invalid version \"1.0 beta\"", 4),
+                (
+                    "unknown key package.mian-class, did you mean main-class?",
+                    5
+                ),
+                (
+                    "unknown section [test_dependencies], did you mean [test-dependencies]?",
+                    8
+                ),
+                (
+                    "invalid coordinates \"org.slf4j\" in dependencies, use \"group:name\"",
+                    12
+                ),
+                (
+                    "dependencies.a:c should have a version, a path or workspace = true",
+                    13
+                ),
+                (
+                    "unknown key dependencies.a:c.verison, did you mean version?",
+                    13
+                ),
+                (
+                    "dependencies.a:d has an invalid version range [1.0, use eg. [1.0,2.0), ^1.2, ~1.2.3 or 1.*",
+                    14
+                ),
+                (
+                    "compile.release should be a string or a number, not a list",
+                    18
+                ),
+            ]
+        );
+
+        let problems = check("[package]\nname = \"b\"\n");
+        assert_eq!(problems[0].message, "package.group is missing");
+        let problems = check("[package]\ngroup = 1979-05-27\nname = \"b\"\nversion = \"1.0\"\n");
+        assert_eq!(
+            problems[0].message,
+            "package.group should be a string, not a date"
+        );
+        assert_eq!(problems[0].span.as_ref().map(|s| s.start), Some(18));
+        let problems = check("[package\n");
+        assert!(problems[0].message.starts_with("invalid table header"));
+        assert_eq!(problems[0].span.as_ref().map(|s| s.start), Some(8));
+    }
+
+    #[test]
+    fn suggestions() {
+        let known = ["dependencies", "test-dependencies", "toolchain"];
+        assert_eq!(
+            suggest("test_dependencies", known.into_iter()),
+            Some("test-dependencies")
+        );
+        assert_eq!(
+            suggest("dependecies", known.into_iter()),
+            Some("dependencies")
+        );
+        assert_eq!(suggest("toolchian", known.into_iter()), Some("toolchain"));
+        assert_eq!(suggest("workspace", known.into_iter()), None);
+    }
+}
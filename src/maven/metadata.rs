@@ -2,8 +2,11 @@ use strong_xml::XmlRead;
 
 use crate::maven::pom::{ArtifactId, GroupId, Version};
 
-/// The Maven variant to parse poms
+/// The Maven variant to parse maven-metadata.xml
 /// These structs is directly modelled after the XML because that is what strong-xml plugin requires
+///
+/// There are two kinds: the one of a snapshot (in the version directory), with the snapshot and
+/// the version, and the one of an artifact (in the directory above), with all versions
 #[derive(XmlRead, PartialEq, Debug)]
 #[xml(tag = "metadata")]
 pub struct Metadata {
@@ -12,7 +15,7 @@ pub struct Metadata {
     #[xml(child = "artifactId")]
     pub artifact_id: ArtifactId,
     #[xml(child = "version")]
-    pub version: Version,
+    pub version: Option<Version>,
     #[xml(child = "versioning")]
     pub versioning: Versioning,
 }
@@ -20,12 +23,53 @@ pub struct Metadata {
 #[derive(XmlRead, PartialEq, Debug)]
 #[xml(tag = "versioning")]
 pub struct Versioning {
+    #[xml(child = "latest")]
+    pub latest: Option<Latest>,
+    #[xml(child = "release")]
+    pub release: Option<Release>,
+    #[xml(child = "versions")]
+    pub versions: Option<Versions>,
     #[xml(child = "snapshot")]
-    pub snapshot: Snapshot,
+    pub snapshot: Option<Snapshot>,
     #[xml(child = "lastUpdated")]
-    pub last_updated: LastUpdated,
+    pub last_updated: Option<LastUpdated>,
     #[xml(child = "snapshotVersions")]
-    pub snapshot_versions: SnapshotVersions,
+    pub snapshot_versions: Option<SnapshotVersions>,
+}
+
+impl Metadata {
+    /// the versions of an artifact, in the order of the file (oldest first)
+    pub fn versions(&self) -> Vec<&str> {
+        self.versioning
+            .versions
+            .iter()
+            .flat_map(|v| &v.versions)
+            .map(|v| v.value.as_str())
+            .collect()
+    }
+}
+
+/// the newest version, including snapshots
+#[derive(XmlRead, PartialEq, Debug)]
+#[xml(tag = "latest")]
+pub struct Latest {
+    #[xml(text)]
+    pub value: String,
+}
+
+/// the newest version that is not a snapshot
+#[derive(XmlRead, PartialEq, Debug)]
+#[xml(tag = "release")]
+pub struct Release {
+    #[xml(text)]
+    pub value: String,
+}
+
+#[derive(XmlRead, PartialEq, Debug)]
+#[xml(tag = "versions")]
+pub struct Versions {
+    #[xml(child = "version")]
+    pub versions: Vec<Version>,
 }
 
 #[derive(XmlRead, PartialEq, Debug)]
@@ -105,3 +149,88 @@ pub struct Value {
     #[xml(text)]
     pub value: String,
 }
+
+#[cfg(test)]
+mod test {
+    use strong_xml::XmlRead;
+
+    use crate::maven::metadata::Metadata;
+
+    #[test]
+    fn parse_artifact_metadata() {
+        let metadata = Metadata::from_str(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>org.slf4j</groupId>
+  <artifactId>slf4j-api</artifactId>
+  <versioning>
+    <latest>2.1.0-SNAPSHOT</latest>
+    <release>2.0.9</release>
+    <versions>
+      <version>1.7.36</version>
+      <version>2.0.9</version>
+      <version>2.1.0-SNAPSHOT</version>
+    </versions>
+    <lastUpdated>20230903173021</lastUpdated>
+  </versioning>
+</metadata>"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.group_id.value, "org.slf4j");
+        assert!(metadata.version.is_none());
+        let versioning = &metadata.versioning;
+        assert_eq!(versioning.latest.as_ref().unwrap().value, "2.1.0-SNAPSHOT");
+        assert_eq!(versioning.release.as_ref().unwrap().value, "2.0.9");
+        assert_eq!(metadata.versions(), ["1.7.36", "2.0.9", "2.1.0-SNAPSHOT"]);
+        assert!(versioning.snapshot.is_none());
+    }
+
+    #[test]
+    fn parse_snapshot_metadata() {
+        let metadata = Metadata::from_str(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata modelVersion="1.1.0">
+  <groupId>org.example</groupId>
+  <artifactId>lib</artifactId>
+  <version>1.0-SNAPSHOT</version>
+  <versioning>
+    <snapshot>
+      <timestamp>20231012.081502</timestamp>
+      <buildNumber>7</buildNumber>
+    </snapshot>
+    <lastUpdated>20231012081502</lastUpdated>
+    <snapshotVersions>
+      <snapshotVersion>
+        <classifier>sources</classifier>
+        <extension>jar</extension>
+        <value>1.0-20231012.081502-7</value>
+        <updated>20231012081502</updated>
+      </snapshotVersion>
+      <snapshotVersion>
+        <extension>pom</extension>
+        <value>1.0-20231012.081502-7</value>
+        <updated>20231012081502</updated>
+      </snapshotVersion>
+    </snapshotVersions>
+  </versioning>
+</metadata>"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.version.as_ref().unwrap().value, "1.0-SNAPSHOT");
+        let snapshot = metadata.versioning.snapshot.as_ref().unwrap();
+        assert_eq!(snapshot.timestamp.value, "20231012.081502");
+        assert_eq!(snapshot.build_number.value, "7");
+        let versions = metadata.versioning.snapshot_versions.as_ref().unwrap();
+        assert_eq!(versions.snapshot_versions.len(), 2);
+        assert_eq!(
+            versions.snapshot_versions[0]
+                .classifier
+                .as_ref()
+                .unwrap()
+                .value,
+            "sources"
+        );
+        assert!(versions.snapshot_versions[1].classifier.is_none());
+        assert!(metadata.versions().is_empty());
+    }
+}
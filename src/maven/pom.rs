@@ -150,7 +150,7 @@ pub struct Developers {
 
 #[derive(XmlRead, PartialEq, Debug)]
 #[xml(tag = "developer")]
-struct Developer {
+pub struct Developer {
     #[xml(child = "id")]
     pub(crate) id: Option<Id>,
     #[xml(child = "name")]
@@ -180,6 +180,24 @@ pub struct Dependency {
     pub(crate) artifact_id: ArtifactId,
     #[xml(child = "version")]
     pub(crate) version: Option<Version>,
+    #[xml(child = "scope")]
+    pub(crate) scope: Option<Scope>,
+    #[xml(child = "optional")]
+    pub(crate) optional: Option<Optional>,
+}
+
+#[derive(XmlRead, PartialEq, Debug, Clone)]
+#[xml(tag = "scope")]
+pub struct Scope {
+    #[xml(text)]
+    pub(crate) value: String,
+}
+
+#[derive(XmlRead, PartialEq, Debug, Clone)]
+#[xml(tag = "optional")]
+pub struct Optional {
+    #[xml(text)]
+    pub(crate) value: String,
 }
 
 #[cfg(test)]
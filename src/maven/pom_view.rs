@@ -3,19 +3,19 @@ use regex::Regex;
 use strong_xml::XmlRead;
 
 use crate::deploader;
+use crate::error::JargoError;
 use crate::maven::pom::{Dependency, Parent, Pom};
 use crate::project::{Artifact, Project};
 
 /// offers a (non-mutable) view on the pom-as-xml-representation
 /// the main use of this is that it resolves the parent information when needed
-pub(crate) struct PomView<'a> {
+pub struct PomView {
     pom: Pom,
-    project: &'a Project,
-    parent: Option<Box<PomView<'a>>>,
+    parent: Option<Box<PomView>>,
 }
 
-impl<'a> PomView<'a> {
-    pub fn new(pom: Pom, project: &'a Project) -> Result<Self, Error> {
+impl PomView {
+    pub fn new(pom: Pom, project: &Project) -> Result<Self, Error> {
         // recursively lookup the parents
         if let Some(parent) = &pom.parent {
             let parent_artifact = Artifact::new(
@@ -31,22 +31,17 @@ impl<'a> PomView<'a> {
 
             Ok(Self {
                 pom,
-                project,
                 parent: Some(Box::new(PomView::new(parent_pom, project)?)),
             })
         } else {
-            Ok(Self {
-                pom,
-                project,
-                parent: None,
-            })
+            Ok(Self { pom, parent: None })
         }
     }
     pub fn model_version(&self) -> String {
         self.pom.model_version.value.clone()
     }
-    pub fn parent(&self) -> Option<ParentView> {
-        self.pom.parent.as_ref().map(|p| ParentView { parent: &p })
+    pub fn parent(&self) -> Option<ParentView<'_>> {
+        self.pom.parent.as_ref().map(|p| ParentView { parent: p })
     }
     pub fn group_id(&self) -> Option<String> {
         //TODO get value from parent
@@ -55,16 +50,12 @@ impl<'a> PomView<'a> {
     pub fn artifact_id(&self) -> &String {
         &self.pom.artifact_id.value
     }
-    pub fn version(&self) -> String {
-        let mut version = &self.pom.version;
-        while version.is_none() {
-            if let Some(parent) = &self.parent {
-                version = &parent.pom.version;
-            }
+    /// the version of the pom, or else of the nearest parent that has one
+    pub fn version(&self) -> Option<String> {
+        match &self.pom.version {
+            Some(version) => Some(version.value.clone()),
+            None => self.parent.as_ref().and_then(|parent| parent.version()),
         }
-        version.as_ref().map(|v| v.value.clone()).unwrap()
-        // unwrap? This is assuming there is always a version,
-        // which sounds plausible but is unproven
         // TODO resolve properties
     }
     pub fn name(&self) -> &String {
@@ -73,6 +64,10 @@ impl<'a> PomView<'a> {
     pub fn packaging(&self) -> Option<String> {
         self.pom.packaging.as_ref().map(|v| v.value.clone())
     }
+    /// poms with packaging 'pom' don't have a jar
+    pub fn is_pom_packaging(&self) -> bool {
+        self.packaging().as_deref() == Some("pom")
+    }
     pub fn url(&self) -> Option<String> {
         self.pom.url.as_ref().map(|v| v.value.clone())
     }
@@ -92,10 +87,12 @@ impl<'a> PomView<'a> {
         }
     }
 
-    pub fn dependencies(&self) -> Vec<DependencyView> {
+    /// the dependencies that are needed transitively, ie. not the optional ones
+    /// and not the ones that are only needed for testing or are provided at runtime
+    pub fn dependencies(&self) -> Result<Vec<DependencyView<'_>>, JargoError> {
         let mut resolved_deps = vec![];
         if let Some(deps) = &self.pom.dependencies {
-            for dep in &deps.value {
+            for dep in deps.value.iter().filter(|d| is_transitive(d)) {
                 let version = if let Some(version) = &dep.version {
                     Some(version.value.clone())
                 } else {
@@ -106,32 +103,39 @@ impl<'a> PomView<'a> {
                         None
                     }
                 };
+                let version = version.ok_or_else(|| JargoError::Resolution {
+                    coordinates: format!("{}:{}", dep.group_id.value, dep.artifact_id.value),
+                    dependency_path: vec![],
+                    message: "no version, also not in the dependencyManagement of the parent"
+                        .into(),
+                })?;
                 resolved_deps.push(DependencyView {
                     group_id: &dep.group_id.value,
                     artifact_id: &dep.artifact_id.value,
-                    version: version.expect(&format!(
-                        "Could not find version for {}:{}",
-                        dep.group_id.value, dep.artifact_id.value
-                    )),
+                    version,
                 })
             }
         }
-        resolved_deps
+        Ok(resolved_deps)
     }
 }
 
 fn resolve_props(pom: &PomView, version: String) -> String {
     let p = Regex::new("\\$\\{(?<prop>.+?)}").expect("wrong regex"); //TODO instantiate once
-    let v2 = if let Some(capture) = p.captures(&version) {
+    if let Some(capture) = p.captures(&version) {
         match &capture["prop"] {
-            "project.version" => pom.version(),
-            _ => pom.
+            "project.version" => pom.version().unwrap_or(version),
+            _ => version.clone(), //TODO resolve properties
         }
     } else {
         version
-    };
-    println!("{}", v2);
-    v2
+    }
+}
+
+fn is_transitive(dep: &Dependency) -> bool {
+    let scope = dep.scope.as_ref().map(|s| s.value.as_str()).unwrap_or("compile");
+    let optional = dep.optional.as_ref().is_some_and(|o| o.value.trim() == "true");
+    !optional && (scope == "compile" || scope == "runtime")
 }
 
 fn search_version(dep: &Dependency, depman: DependencyManagementView) -> Option<String> {
@@ -143,7 +147,7 @@ fn search_version(dep: &Dependency, depman: DependencyManagementView) -> Option<
     None
 }
 
-struct ParentView<'a> {
+pub struct ParentView<'a> {
     parent: &'a Parent,
 }
 
@@ -161,13 +165,13 @@ impl<'a> ParentView<'a> {
 }
 
 // a copied view
-pub(crate) struct DependencyView<'a> {
+pub struct DependencyView<'a> {
     artifact_id: &'a String,
     group_id: &'a String,
     version: String,
 }
 
-pub(crate) struct DependencyManagementView {
+pub struct DependencyManagementView {
     dependencies: Vec<Dependency>,
 }
 
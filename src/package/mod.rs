@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+use colored::Colorize;
+use sha1::{Digest, Sha1};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::compile::TARGET_MAIN;
+use crate::deploader::Classpath;
+use crate::project::{PackageKind, Project};
+
+mod shade;
+mod uber;
+
+pub const MANIFEST: &str = "META-INF/MANIFEST.MF";
+
+/// runs the package stage
+///
+/// creates target/{name}-{version}.jar from target/classes, or when an uber jar is requested
+/// target/{name}-{version}-uber.jar which also contains all runtime dependencies
+pub fn run(project: &Project, classpath: &Classpath, kind: PackageKind) -> Result<PathBuf, Error> {
+    let target = PathBuf::from(&project.target_dir);
+    let jar_path = match kind {
+        PackageKind::Jar => target.join(format!("{}-{}.jar", project.name, project.version)),
+        PackageKind::Uber => target.join(format!("{}-{}-uber.jar", project.name, project.version)),
+    };
+    println!(
+        "{} {}",
+        "Packaging".green(),
+        jar_path.to_str().unwrap_or_default()
+    );
+
+    let mut jar = JarWriter::create(&jar_path)?;
+    jar.write_manifest(project)?;
+    let classes = target.join(TARGET_MAIN);
+    match kind {
+        PackageKind::Jar => jar.add_dir(&classes, TARGET_MAIN)?,
+        PackageKind::Uber => uber::add_all(
+            &mut jar,
+            &classes,
+            classpath.runtime(),
+            &project.relocations,
+        )?,
+    }
+    jar.finish()?;
+    Ok(jar_path)
+}
+
+/// thin wrapper around the zip writer that takes care of the directory entries
+/// and remembers where every entry came from
+pub(crate) struct JarWriter {
+    zip: ZipWriter<File>,
+    dirs: HashSet<String>,
+    files: HashMap<String, WrittenEntry>,
+}
+
+/// origin is the jar (or directory) the entry was copied from
+pub(crate) struct WrittenEntry {
+    pub(crate) origin: String,
+    pub(crate) digest: Vec<u8>,
+}
+
+impl JarWriter {
+    fn create(path: &Path) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            zip: ZipWriter::new(File::create(path)?),
+            dirs: HashSet::new(),
+            files: HashMap::new(),
+        })
+    }
+
+    fn write_manifest(&mut self, project: &Project) -> Result<(), Error> {
+        let mut manifest = String::from("Manifest-Version: 1.0\r\nCreated-By: jargo\r\n");
+        if let Some(main_class) = &project.main_class {
+            manifest.push_str(&format!("Main-Class: {}\r\n", main_class));
+        }
+        manifest.push_str("\r\n");
+        self.add_entry(MANIFEST, manifest.as_bytes(), "jargo")
+    }
+
+    /// adds all files under dir, with their names relative to dir, except a manifest from the
+    /// resources, because the one jargo writes is already in the jar
+    fn add_dir(&mut self, dir: &Path, origin: &str) -> Result<(), Error> {
+        for (name, file) in dir_entries(dir)? {
+            if name.to_uppercase() == MANIFEST {
+                continue;
+            }
+            self.add_entry(&name, &fs::read(&file)?, origin)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&WrittenEntry> {
+        self.files.get(name)
+    }
+
+    /// writes a file entry, creating the parent directory entries as needed
+    pub(crate) fn add_entry(
+        &mut self,
+        name: &str,
+        contents: &[u8],
+        origin: &str,
+    ) -> Result<(), Error> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut parent = String::new();
+        for part in name.split('/').collect::<Vec<_>>().split_last().unwrap().1 {
+            parent.push_str(part);
+            parent.push('/');
+            if self.dirs.insert(parent.clone()) {
+                self.zip.add_directory(parent.as_str(), options)?;
+            }
+        }
+        self.zip.start_file(name, options)?;
+        self.zip.write_all(contents)?;
+        self.files.insert(
+            name.into(),
+            WrittenEntry {
+                origin: origin.into(),
+                digest: digest(contents),
+            },
+        );
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        self.zip.finish()?;
+        Ok(())
+    }
+}
+
+pub(crate) fn digest(contents: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha1::new();
+    hasher.update(contents);
+    hasher.finalize().to_vec()
+}
+
+/// lists the files under dir, sorted, together with their jar entry names
+pub(crate) fn dir_entries(dir: &Path) -> Result<Vec<(String, PathBuf)>, Error> {
+    let mut files = vec![];
+    if dir.exists() {
+        walk(dir, &mut files)?;
+    }
+    files.sort();
+    files
+        .into_iter()
+        .map(|file| {
+            let name = file
+                .strip_prefix(dir)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Ok((name, file))
+        })
+        .collect()
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.metadata()?.is_dir() {
+            walk(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
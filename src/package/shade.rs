@@ -0,0 +1,417 @@
+use anyhow::Error;
+
+use crate::classfile::ClassFile;
+use crate::project::Relocation;
+
+const SERVICES: &str = "META-INF/services/";
+
+/// Moves packages to another name, in the jar entry names as well as in the classes that refer to them.
+///
+/// In the class files all names, descriptors, (generic) signatures, annotations and string literals
+/// are Utf8 constants in the constant pool, so rewriting those is enough.
+pub(crate) struct Relocator {
+    /// (from, to) with both the slashed and the dotted variants, longest first
+    patterns: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Relocator {
+    pub(crate) fn new(relocations: &[Relocation]) -> Self {
+        let mut patterns = vec![];
+        for relocation in relocations {
+            for separator in ["/", "."] {
+                patterns.push((
+                    relocation.from.replace('.', separator).into_bytes(),
+                    relocation.to.replace('.', separator).into_bytes(),
+                ));
+            }
+        }
+        patterns.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        Self { patterns }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// the name of the entry in the jar, ie. the path of a class or the name of a services file
+    pub(crate) fn entry_name(&self, name: &str) -> String {
+        if let Some(service) = name.strip_prefix(SERVICES) {
+            format!("{}{}", SERVICES, self.text(service))
+        } else {
+            self.text(name)
+        }
+    }
+
+    pub(crate) fn class(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut class = ClassFile::parse(bytes)?;
+        let mut changed = false;
+        for value in class.utf8_constants_mut() {
+            if let Some(relocated) = self.relocate(value) {
+                *value = relocated;
+                changed = true;
+            }
+        }
+        if changed {
+            class.to_bytes()
+        } else {
+            Ok(bytes.to_vec())
+        }
+    }
+
+    pub(crate) fn text(&self, text: &str) -> String {
+        match self.relocate(text.as_bytes()) {
+            Some(relocated) => String::from_utf8_lossy(&relocated).into_owned(),
+            None => text.to_owned(),
+        }
+    }
+
+    /// replaces the packages where they start a name, returns None when nothing has changed
+    fn relocate(&self, value: &[u8]) -> Option<Vec<u8>> {
+        // in a descriptor or signature only the class names are names,
+        // ILcom/example/Foo; has one after the I
+        if let Some(signature) = Signature::relocate(self, value) {
+            return signature;
+        }
+        let mut result = Vec::with_capacity(value.len());
+        let mut changed = false;
+        let mut i = 0;
+        'outer: while i < value.len() {
+            if i == 0 || !is_name_part(value[i - 1]) {
+                if let Some((end, to)) = self.matching(value, i, value.len()) {
+                    result.extend_from_slice(to);
+                    i = end;
+                    changed = true;
+                    continue 'outer;
+                }
+            }
+            result.push(value[i]);
+            i += 1;
+        }
+        if changed {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    /// the pattern that matches whole names at the start of value[start..end], with where it ends
+    fn matching(&self, value: &[u8], start: usize, end: usize) -> Option<(usize, &[u8])> {
+        self.patterns.iter().find_map(|(from, to)| {
+            let from_end = start + from.len();
+            (value[start..end].starts_with(from)
+                && (from_end == end || !is_identifier(value[from_end])))
+            .then_some((from_end, to.as_slice()))
+        })
+    }
+}
+
+/// Walks a descriptor or (generic) signature with its grammar (JVMS 4.3 and 4.7.9.1), like
+/// (ILcom/example/Foo;)V, <T:Ljava/lang/Object;>(TT;)V or Ljava/util/List<+Lcom/example/Foo;>;
+struct Signature<'a> {
+    relocator: &'a Relocator,
+    value: &'a [u8],
+    position: usize,
+    result: Vec<u8>,
+    changed: bool,
+}
+
+impl<'a> Signature<'a> {
+    /// None when the value is not a descriptor or signature, Some(None) when nothing has changed
+    fn relocate(relocator: &'a Relocator, value: &'a [u8]) -> Option<Option<Vec<u8>>> {
+        let mut signature = Signature {
+            relocator,
+            value,
+            position: 0,
+            result: Vec::with_capacity(value.len()),
+            changed: false,
+        };
+        signature.signature()?;
+        Some(signature.changed.then_some(signature.result))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.value.get(self.position).copied()
+    }
+
+    fn advance(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.result.push(c);
+        self.position += 1;
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<()> {
+        (self.advance()? == expected).then_some(())
+    }
+
+    /// a field or method descriptor, or a class, method or field signature
+    fn signature(&mut self) -> Option<()> {
+        if self.peek()? == b'<' {
+            self.type_parameters()?;
+        }
+        if self.peek()? == b'(' {
+            self.advance();
+            while self.peek()? != b')' {
+                self.java_type()?;
+            }
+            self.advance();
+            if self.peek()? == b'V' {
+                self.advance();
+            } else {
+                self.java_type()?;
+            }
+            while self.peek() == Some(b'^') {
+                self.advance();
+                self.reference_type()?;
+            }
+        } else {
+            // a field type, or a superclass and the interfaces
+            let class = self.peek()? == b'L';
+            self.java_type()?;
+            while class && self.peek().is_some() {
+                self.class_type()?;
+            }
+        }
+        self.peek().is_none().then_some(())
+    }
+
+    /// <T:Ljava/lang/Object;U::Ljava/lang/Comparable<TU;>;>
+    fn type_parameters(&mut self) -> Option<()> {
+        self.expect(b'<')?;
+        while self.peek()? != b'>' {
+            self.identifier(b":")?;
+            while self.peek()? == b':' {
+                self.advance();
+                // the class bound can be empty
+                if matches!(self.peek()?, b'L' | b'T' | b'[') {
+                    self.reference_type()?;
+                }
+            }
+        }
+        self.advance();
+        Some(())
+    }
+
+    fn java_type(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => {
+                self.advance();
+                Some(())
+            }
+            _ => self.reference_type(),
+        }
+    }
+
+    fn reference_type(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'L' => self.class_type(),
+            b'T' => {
+                self.advance();
+                self.identifier(b";")?;
+                self.expect(b';')
+            }
+            b'[' => {
+                self.advance();
+                self.java_type()
+            }
+            _ => None,
+        }
+    }
+
+    /// Lcom/example/Outer<TT;>.Inner;, only the first part is a name that can be relocated
+    fn class_type(&mut self) -> Option<()> {
+        self.expect(b'L')?;
+        let start = self.position;
+        let end = start
+            + self.value[start..]
+                .iter()
+                .position(|c| b";<.".contains(c))?;
+        if start == end || self.value[start..end].iter().any(|c| b"()[>:".contains(c)) {
+            return None;
+        }
+        match self.relocator.matching(self.value, start, end) {
+            Some((from_end, to)) => {
+                self.result.extend_from_slice(to);
+                self.result.extend_from_slice(&self.value[from_end..end]);
+                self.changed = true;
+            }
+            None => self.result.extend_from_slice(&self.value[start..end]),
+        }
+        self.position = end;
+        loop {
+            match self.advance()? {
+                b';' => return Some(()),
+                b'<' => {
+                    while self.peek()? != b'>' {
+                        match self.peek()? {
+                            b'*' => {
+                                self.advance();
+                            }
+                            b'+' | b'-' => {
+                                self.advance();
+                                self.reference_type()?;
+                            }
+                            _ => self.reference_type()?,
+                        }
+                    }
+                    self.advance();
+                }
+                // the simple name of an inner class
+                _ => self.identifier(b";<.")?,
+            }
+        }
+    }
+
+    /// a non-empty name up to one of the terminators, which is not consumed
+    fn identifier(&mut self, terminators: &[u8]) -> Option<()> {
+        let start = self.position;
+        while !terminators.contains(&self.peek()?) {
+            if b";<>/:.[(".contains(&self.peek()?) {
+                return None;
+            }
+            self.advance();
+        }
+        (self.position > start).then_some(())
+    }
+}
+
+fn is_name_part(c: u8) -> bool {
+    is_identifier(c) || c == b'/' || c == b'.'
+}
+
+fn is_identifier(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'$'
+}
+
+#[cfg(test)]
+mod test {
+    use crate::package::shade::Relocator;
+    use crate::project::Relocation;
+
+    fn relocator() -> Relocator {
+        Relocator::new(&[Relocation {
+            from: "com.google.common".into(),
+            to: "our.shaded.guava".into(),
+        }])
+    }
+
+    #[test]
+    fn relocate_names() {
+        let relocator = relocator();
+        assert_eq!(
+            relocator.text("com/google/common/base/Strings"),
+            "our/shaded/guava/base/Strings"
+        );
+        assert_eq!(
+            relocator.text("(Lcom/google/common/collect/ImmutableList;[Lcom/google/common/Foo;)V"),
+            "(Lour/shaded/guava/collect/ImmutableList;[Lour/shaded/guava/Foo;)V"
+        );
+        assert_eq!(
+            relocator.text("Ljava/util/List<Lcom/google/common/Foo;>;"),
+            "Ljava/util/List<Lour/shaded/guava/Foo;>;"
+        );
+        assert_eq!(
+            relocator.text("com.google.common.base.Strings"),
+            "our.shaded.guava.base.Strings"
+        );
+        assert_eq!(relocator.text("com.google.common"), "our.shaded.guava");
+    }
+
+    #[test]
+    fn relocate_descriptors() {
+        let relocator = relocator();
+        let descriptors = [
+            ("(ILcom/google/common/Foo;)V", "(ILour/shaded/guava/Foo;)V"),
+            (
+                "(JZLcom/google/common/Foo;[[BLcom/google/common/Bar;)Lcom/google/common/Baz;",
+                "(JZLour/shaded/guava/Foo;[[BLour/shaded/guava/Bar;)Lour/shaded/guava/Baz;",
+            ),
+            ("[JLcom/google/common/Foo;", "[JLcom/google/common/Foo;"),
+            ("Lcom/google/common/Foo;", "Lour/shaded/guava/Foo;"),
+            ("(DLjava/lang/String;)I", "(DLjava/lang/String;)I"),
+        ];
+        for (descriptor, relocated) in descriptors {
+            assert_eq!(relocator.text(descriptor), relocated, "{}", descriptor);
+        }
+    }
+
+    #[test]
+    fn relocate_generic_signatures() {
+        let relocator = relocator();
+        let signatures = [
+            (
+                "<T:Ljava/lang/Object;U::Lcom/google/common/Foo<TT;>;>(ITT;Ljava/util/List<+Lcom/google/common/Bar;>;)TU;^Lcom/google/common/Failure;",
+                "<T:Ljava/lang/Object;U::Lour/shaded/guava/Foo<TT;>;>(ITT;Ljava/util/List<+Lour/shaded/guava/Bar;>;)TU;^Lour/shaded/guava/Failure;",
+            ),
+            (
+                "<K:Ljava/lang/Object;>Lcom/google/common/Base<TK;>;Ljava/util/Map<TK;*>;",
+                "<K:Ljava/lang/Object;>Lour/shaded/guava/Base<TK;>;Ljava/util/Map<TK;*>;",
+            ),
+            (
+                "Lcom/google/common/Outer<[I>.Inner<-Lcom/google/common/Foo;>;",
+                "Lour/shaded/guava/Outer<[I>.Inner<-Lour/shaded/guava/Foo;>;",
+            ),
+        ];
+        for (signature, relocated) in signatures {
+            assert_eq!(relocator.text(signature), relocated, "{}", signature);
+        }
+    }
+
+    #[test]
+    fn leave_other_names() {
+        let relocator = relocator();
+        assert_eq!(
+            relocator.text("com/google/commonmark/Node"),
+            "com/google/commonmark/Node"
+        );
+        assert_eq!(
+            relocator.text("org/com/google/common/Foo"),
+            "org/com/google/common/Foo"
+        );
+        assert_eq!(
+            relocator.text("XLcom/google/common/Foo"),
+            "XLcom/google/common/Foo"
+        );
+    }
+
+    #[test]
+    fn relocate_entries() {
+        let relocator = relocator();
+        assert_eq!(
+            relocator.entry_name("META-INF/services/com.google.common.Service"),
+            "META-INF/services/our.shaded.guava.Service"
+        );
+        assert_eq!(
+            relocator.entry_name("com/google/common/base/Strings.class"),
+            "our/shaded/guava/base/Strings.class"
+        );
+    }
+
+    #[test]
+    fn relocate_class() {
+        let relocator = Relocator::new(&[Relocation {
+            from: "com.example".into(),
+            to: "our.shaded.example".into(),
+        }]);
+        let class = relocator
+            .class(&crate::classfile::test::sample_class())
+            .unwrap();
+        let mut class = crate::classfile::ClassFile::parse(&class).unwrap();
+        assert_eq!(class.this_class().unwrap(), "our/shaded/example/B");
+        assert_eq!(
+            class.class_references(),
+            vec![
+                "our/shaded/example/B",
+                "java/lang/Object",
+                "our/shaded/example/C"
+            ]
+        );
+        let constants: Vec<String> = class
+            .utf8_constants_mut()
+            .map(|c| String::from_utf8_lossy(c).into_owned())
+            .collect();
+        assert!(constants.contains(&"our.shaded.example.C".to_owned()));
+        assert!(constants.contains(&"(ILour/shaded/example/C;J)V".to_owned()));
+        assert!(constants.contains(&"B.java".to_owned()));
+    }
+}
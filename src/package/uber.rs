@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Error;
+use colored::Colorize;
+use zip::ZipArchive;
+
+use crate::compile::TARGET_MAIN;
+use crate::package::shade::Relocator;
+use crate::package::{digest, dir_entries, JarWriter, MANIFEST};
+use crate::project::Relocation;
+
+const SPRING_FACTORIES: &str = "META-INF/spring.factories";
+
+/// Adds the project classes and the contents of all dependency jars (and the classes of the projects
+/// it depends on) to the uber jar.
+///
+/// Service registrations (META-INF/services/*, spring.factories and spring's *.imports files)
+/// are merged, signature files are dropped because they are invalid for the new jar
+/// and for any other entry that occurs more than once the first one wins.
+/// Classes that occur more than once with different contents are reported.
+/// Finally, all entries are relocated as configured in [shade].
+pub(super) fn add_all(
+    jar: &mut JarWriter,
+    classes: &Path,
+    dependencies: &[String],
+    relocations: &[Relocation],
+) -> Result<(), Error> {
+    let mut uber = Uber::new(Relocator::new(relocations));
+    for (name, file) in dir_entries(classes)? {
+        uber.add(jar, &name, fs::read(file)?, TARGET_MAIN)?;
+    }
+    for dependency in dependencies {
+        // the classes of another project in the workspace
+        if Path::new(dependency).is_dir() {
+            for (name, file) in dir_entries(Path::new(dependency))? {
+                uber.add(jar, &name, fs::read(file)?, dependency)?;
+            }
+            continue;
+        }
+        let origin = Path::new(dependency)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| dependency.clone());
+        let mut archive = ZipArchive::new(File::open(dependency)?)?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name().to_owned();
+            let mut contents = vec![];
+            entry.read_to_end(&mut contents)?;
+            uber.add(jar, &name, contents, &origin)?;
+        }
+    }
+    uber.write_merged(jar)?;
+    uber.report_conflicts();
+    Ok(())
+}
+
+/// what to do with an entry
+#[derive(Debug, PartialEq)]
+enum EntryKind {
+    Skip,
+    Lines,
+    SpringFactories,
+    Class,
+    Other,
+}
+
+impl EntryKind {
+    fn of(name: &str) -> Self {
+        let upper = name.to_uppercase();
+        if upper == MANIFEST || upper == "META-INF/INDEX.LIST" || is_signature(&upper) {
+            EntryKind::Skip
+        } else if name == "module-info.class" || name.ends_with("/module-info.class") {
+            // a module descriptor from one jar doesn't describe the uber jar
+            EntryKind::Skip
+        } else if name.starts_with("META-INF/services/")
+            || (name.starts_with("META-INF/spring/") && name.ends_with(".imports"))
+        {
+            EntryKind::Lines
+        } else if name == SPRING_FACTORIES {
+            EntryKind::SpringFactories
+        } else if name.ends_with(".class") {
+            EntryKind::Class
+        } else {
+            EntryKind::Other
+        }
+    }
+}
+
+fn is_signature(upper_name: &str) -> bool {
+    if let Some(file) = upper_name.strip_prefix("META-INF/") {
+        !file.contains('/')
+            && (file.ends_with(".SF")
+                || file.ends_with(".RSA")
+                || file.ends_with(".DSA")
+                || file.ends_with(".EC")
+                || file.starts_with("SIG-"))
+    } else {
+        false
+    }
+}
+
+/// a class that was found in more than one place
+#[derive(Debug)]
+struct Conflict {
+    class: String,
+    used: String,
+    ignored: String,
+}
+
+/// The state that is kept while the uber jar is assembled.
+/// Merged entries are only written when all jars have been read.
+struct Uber {
+    relocator: Relocator,
+    lines: BTreeMap<String, Vec<String>>,
+    factories: Vec<(String, Vec<String>)>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Uber {
+    fn new(relocator: Relocator) -> Self {
+        Self {
+            relocator,
+            lines: BTreeMap::new(),
+            factories: vec![],
+            conflicts: vec![],
+        }
+    }
+
+    fn add(
+        &mut self,
+        jar: &mut JarWriter,
+        name: &str,
+        contents: Vec<u8>,
+        origin: &str,
+    ) -> Result<(), Error> {
+        let kind = EntryKind::of(name);
+        let (name, contents) = if self.relocator.is_empty() {
+            (name.to_owned(), contents)
+        } else {
+            let contents = match kind {
+                EntryKind::Class => self.relocator.class(&contents)?,
+                EntryKind::Lines | EntryKind::SpringFactories => self
+                    .relocator
+                    .text(&String::from_utf8_lossy(&contents))
+                    .into_bytes(),
+                _ => contents,
+            };
+            (self.relocator.entry_name(name), contents)
+        };
+        let name = name.as_str();
+        match kind {
+            EntryKind::Skip => {}
+            EntryKind::Lines => self.merge_lines(name, &String::from_utf8_lossy(&contents)),
+            EntryKind::SpringFactories => self.merge_factories(&String::from_utf8_lossy(&contents)),
+            kind => {
+                if let Some(existing) = jar.get(name) {
+                    if kind == EntryKind::Class && existing.digest != digest(&contents) {
+                        self.conflicts.push(Conflict {
+                            class: name.into(),
+                            used: existing.origin.clone(),
+                            ignored: origin.into(),
+                        });
+                    }
+                } else {
+                    jar.add_entry(name, &contents, origin)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// every line is a class name, comments and duplicates are removed
+    fn merge_lines(&mut self, name: &str, text: &str) {
+        let lines = self.lines.entry(name.into()).or_default();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if !line.is_empty() && !lines.iter().any(|l| l == line) {
+                lines.push(line.into());
+            }
+        }
+    }
+
+    /// spring.factories is a properties file with comma separated class names as values
+    fn merge_factories(&mut self, text: &str) {
+        for (key, value) in parse_properties(text) {
+            let index = match self.factories.iter().position(|(k, _)| *k == key) {
+                Some(index) => index,
+                None => {
+                    self.factories.push((key, vec![]));
+                    self.factories.len() - 1
+                }
+            };
+            let values = &mut self.factories[index].1;
+            for value in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                if !values.iter().any(|v| v == value) {
+                    values.push(value.into());
+                }
+            }
+        }
+    }
+
+    fn write_merged(&self, jar: &mut JarWriter) -> Result<(), Error> {
+        for (name, lines) in &self.lines {
+            let mut text = lines.join("\n");
+            text.push('\n');
+            jar.add_entry(name, text.as_bytes(), "jargo")?;
+        }
+        if !self.factories.is_empty() {
+            jar.add_entry(SPRING_FACTORIES, self.factories_text().as_bytes(), "jargo")?;
+        }
+        Ok(())
+    }
+
+    fn factories_text(&self) -> String {
+        let mut text = String::new();
+        for (key, values) in &self.factories {
+            text.push_str(&format!("{}=\\\n{}\n", key, values.join(",\\\n")));
+        }
+        text
+    }
+
+    /// prints the conflicts, grouped by the jars they occur in
+    fn report_conflicts(&self) {
+        let mut grouped: BTreeMap<(&str, &str), Vec<&str>> = BTreeMap::new();
+        for conflict in &self.conflicts {
+            grouped
+                .entry((&conflict.used, &conflict.ignored))
+                .or_default()
+                .push(&conflict.class);
+        }
+        for ((used, ignored), classes) in grouped {
+            println!(
+                "{} {} classes in {} are also in {}, using the latter",
+                "Duplicate".yellow(),
+                classes.len(),
+                ignored,
+                used
+            );
+            for class in classes.iter().take(5) {
+                println!("    {}", class);
+            }
+            if classes.len() > 5 {
+                println!("    and {} more", classes.len() - 5);
+            }
+        }
+    }
+}
+
+/// parses java properties, only as far as needed for spring.factories
+fn parse_properties(text: &str) -> Vec<(String, String)> {
+    let mut properties = vec![];
+    let mut logical_line = String::new();
+    for line in text.lines() {
+        let line = line.trim_start();
+        if logical_line.is_empty() && (line.starts_with('#') || line.starts_with('!')) {
+            continue;
+        }
+        if let Some(continued) = line.strip_suffix('\\') {
+            logical_line.push_str(continued);
+            continue;
+        }
+        logical_line.push_str(line);
+        if let Some(split) = logical_line.find(['=', ':']) {
+            let (key, value) = logical_line.split_at(split);
+            properties.push((key.trim().into(), value[1..].trim().into()));
+        }
+        logical_line.clear();
+    }
+    properties
+}
+
+#[cfg(test)]
+mod test {
+    use crate::package::shade::Relocator;
+    use crate::package::uber::{EntryKind, Uber};
+
+    #[test]
+    fn classify_entries() {
+        assert_eq!(EntryKind::of("META-INF/MANIFEST.MF"), EntryKind::Skip);
+        assert_eq!(EntryKind::of("META-INF/BCKEY.SF"), EntryKind::Skip);
+        assert_eq!(EntryKind::of("META-INF/bckey.rsa"), EntryKind::Skip);
+        assert_eq!(
+            EntryKind::of("META-INF/versions/9/module-info.class"),
+            EntryKind::Skip
+        );
+        assert_eq!(
+            EntryKind::of("META-INF/services/java.sql.Driver"),
+            EntryKind::Lines
+        );
+        assert_eq!(
+            EntryKind::of(
+                "META-INF/spring/org.springframework.boot.autoconfigure.AutoConfiguration.imports"
+            ),
+            EntryKind::Lines
+        );
+        assert_eq!(
+            EntryKind::of("META-INF/spring.factories"),
+            EntryKind::SpringFactories
+        );
+        assert_eq!(EntryKind::of("com/example/Main.class"), EntryKind::Class);
+        assert_eq!(EntryKind::of("application.yml"), EntryKind::Other);
+    }
+
+    #[test]
+    fn merge_services() {
+        let mut uber = Uber::new(Relocator::new(&[]));
+        uber.merge_lines("META-INF/services/a.B", "# comment\na.C\na.D # inline\n");
+        uber.merge_lines("META-INF/services/a.B", "a.D\n\na.E\n");
+        assert_eq!(
+            uber.lines["META-INF/services/a.B"],
+            vec!["a.C", "a.D", "a.E"]
+        );
+    }
+
+    #[test]
+    fn merge_spring_factories() {
+        let mut uber = Uber::new(Relocator::new(&[]));
+        uber.merge_factories(
+            "# Initializers\norg.springframework.context.ApplicationContextInitializer=\\\na.One,\\\na.Two\n",
+        );
+        uber.merge_factories("org.springframework.context.ApplicationContextInitializer=a.Two,a.Three\nb.Key=b.Value\n");
+        assert_eq!(
+            uber.factories_text(),
+            "org.springframework.context.ApplicationContextInitializer=\\\na.One,\\\na.Two,\\\na.Three\nb.Key=\\\nb.Value\n"
+        );
+    }
+}
@@ -1,7 +1,15 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::maven::pom::Dependency;
+use crate::checksum::ChecksumPolicy;
+use crate::error::JargoError;
+use crate::manifest;
+use crate::toolchain::{self, JavaRequest, Jdk};
+use crate::version::Version;
+use crate::workspace::WorkspaceConfig;
 use anyhow::{anyhow, Error};
 use toml::{Table, Value};
 
@@ -13,16 +21,316 @@ pub struct Project {
     pub version: String,
     pub main_dependencies: Vec<Artifact>,
     pub test_dependencies: Vec<Artifact>,
+    /// the projects this project depends on with `path = "../core"`
+    pub path_dependencies: Vec<PathDependency>,
+    /// the projects the tests depend on with `path = "../testkit"`
+    pub test_path_dependencies: Vec<PathDependency>,
+    /// the [annotation-processors], resolved to the -processorpath
+    pub annotation_processors: Vec<Artifact>,
     pub project_root: String,
-    pub repositories: Vec<String>,
+    /// target/ in the project root, or target/<member> in the root of its workspace
+    pub target_dir: String,
+    /// maven central and the [repositories]
+    pub repositories: Vec<Repository>,
+    /// `checksums` in [resolver], for the repositories that don't set their own
+    pub checksums: ChecksumPolicy,
+    pub kind: PackageKind,
+    pub main_class: Option<String>,
+    pub relocations: Vec<Relocation>,
+    pub test: TestConfig,
+    pub run: RunConfig,
+    pub resources: Resources,
+    /// the [compile] table
+    pub compile: CompileOptions,
+    /// the [compile] table with [compile.test] on top of it
+    pub test_compile: CompileOptions,
+    /// the java version from [package] or [toolchain]
+    pub java: Option<JavaRequest>,
+    /// the JDK that matches the java version, None to use the javac and java on the PATH
+    pub jdk: Option<Jdk>,
+}
+
+impl Project {
+    /// javac of the JDK of the project
+    pub fn javac(&self) -> Command {
+        match &self.jdk {
+            Some(jdk) => Command::new(jdk.javac()),
+            None => Command::new("javac"),
+        }
+    }
+
+    /// java of the JDK of the project
+    pub fn java(&self) -> Command {
+        match &self.jdk {
+            Some(jdk) => Command::new(jdk.java()),
+            None => Command::new("java"),
+        }
+    }
+}
+
+/// Moves a package to another name in an uber jar, so that it can't clash with other versions
+#[derive(Debug, Clone)]
+pub struct Relocation {
+    pub from: String,
+    pub to: String,
+}
+
+/// The kind of jar that is created when packaging
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum PackageKind {
+    /// only the project classes
+    #[default]
+    Jar,
+    /// the project classes together with all runtime dependencies
+    Uber,
+}
+
+impl PackageKind {
+    fn parse(kind: &str) -> Result<Self, Error> {
+        match kind {
+            "jar" => Ok(PackageKind::Jar),
+            "uber" | "fat" => Ok(PackageKind::Uber),
+            _ => Err(anyhow!(
+                "unknown package kind {}, use 'jar' or 'uber'",
+                kind
+            )),
+        }
+    }
+}
+
+/// a maven repository to download dependencies from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repository {
+    /// the key in [repositories], or central
+    pub name: String,
+    pub url: String,
+    /// what to do with downloads that have no checksum, or a checksum that doesn't match
+    pub checksums: ChecksumPolicy,
+    /// how often to check for a new version of a snapshot
+    pub snapshots: UpdatePolicy,
+    /// how often to check for new versions of an artifact with a version range
+    pub releases: UpdatePolicy,
+}
+
+/// How often the maven-metadata.xml of a snapshot, or with the versions of an artifact, is
+/// downloaded again, like in maven. In between, the one that was downloaded the last time is used.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum UpdatePolicy {
+    /// every build
+    Always,
+    /// the first build of the day (UTC)
+    #[default]
+    Daily,
+    /// when the last check is this many minutes ago
+    Interval(u64),
+    /// only when it was never downloaded
+    Never,
+}
+
+impl UpdatePolicy {
+    pub fn parse(policy: &str) -> Result<Self, Error> {
+        match policy {
+            "always" => Ok(UpdatePolicy::Always),
+            "daily" => Ok(UpdatePolicy::Daily),
+            "never" => Ok(UpdatePolicy::Never),
+            _ => policy
+                .strip_prefix("interval:")
+                .and_then(|minutes| minutes.parse().ok())
+                .map(UpdatePolicy::Interval)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unknown update policy {}, use 'always', 'daily', 'interval:N' (minutes) or 'never'",
+                        policy
+                    )
+                }),
+        }
+    }
+
+    /// whether to check again, given when the last check was
+    pub fn is_due(&self, last_checked: Option<SystemTime>, now: SystemTime) -> bool {
+        let Some(last_checked) = last_checked else {
+            return true;
+        };
+        let elapsed = now.duration_since(last_checked).unwrap_or_default();
+        match self {
+            UpdatePolicy::Always => true,
+            UpdatePolicy::Daily => {
+                let day = |time: SystemTime| {
+                    time.duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs()
+                        / (24 * 60 * 60)
+                };
+                day(last_checked) < day(now)
+            }
+            UpdatePolicy::Interval(minutes) => elapsed >= Duration::from_secs(minutes * 60),
+            UpdatePolicy::Never => false,
+        }
+    }
+}
+
+/// The javac options from the [compile] table.
+/// Options that are not set are left to javac.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompileOptions {
+    /// --release, eg. "17"
+    pub release: Option<String>,
+    /// -encoding of the source files
+    pub encoding: Option<String>,
+    /// -parameters, to keep the names of method parameters
+    pub parameters: Option<bool>,
+    /// the -Xlint keys, eg. ["all", "-serial"], an empty list for plain -Xlint
+    pub lint: Option<Vec<String>>,
+    /// -Werror
+    pub warnings_as_errors: Option<bool>,
+    /// -g: "all", "none", or a list like "lines,source"
+    pub debug: Option<String>,
+    /// the -A options for the annotation processors
+    pub processor_options: Option<BTreeMap<String, String>>,
+    /// any other arguments for javac
+    pub args: Option<Vec<String>>,
+}
+
+impl CompileOptions {
+    /// these options, with the ones that are set in `overrides` replacing them
+    pub fn with(&self, overrides: &CompileOptions) -> CompileOptions {
+        CompileOptions {
+            release: overrides.release.clone().or(self.release.clone()),
+            encoding: overrides.encoding.clone().or(self.encoding.clone()),
+            parameters: overrides.parameters.or(self.parameters),
+            lint: overrides.lint.clone().or(self.lint.clone()),
+            warnings_as_errors: overrides.warnings_as_errors.or(self.warnings_as_errors),
+            debug: overrides.debug.clone().or(self.debug.clone()),
+            processor_options: overrides
+                .processor_options
+                .clone()
+                .or(self.processor_options.clone()),
+            args: overrides.args.clone().or(self.args.clone()),
+        }
+    }
+
+    pub fn javac_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(release) = &self.release {
+            args.push("--release".to_owned());
+            args.push(release.clone());
+        }
+        if let Some(encoding) = &self.encoding {
+            args.push("-encoding".to_owned());
+            args.push(encoding.clone());
+        }
+        if self.parameters == Some(true) {
+            args.push("-parameters".to_owned());
+        }
+        match self.lint.as_deref() {
+            Some([]) => args.push("-Xlint".to_owned()),
+            Some(keys) => args.push(format!("-Xlint:{}", keys.join(","))),
+            None => {}
+        }
+        if self.warnings_as_errors == Some(true) {
+            args.push("-Werror".to_owned());
+        }
+        match self.debug.as_deref() {
+            Some("all") => args.push("-g".to_owned()),
+            Some(debug) => args.push(format!("-g:{}", debug)),
+            None => {}
+        }
+        for (key, value) in self.processor_options.iter().flatten() {
+            args.push(format!("-A{}={}", key, value));
+        }
+        args.extend(self.args.iter().flatten().cloned());
+        args
+    }
+}
+
+/// The [resources] table
+#[derive(Debug, Clone, Default)]
+pub struct Resources {
+    /// globs for the resources in which ${...} placeholders are replaced, eg. "**/*.yml"
+    pub filter: Vec<String>,
+    /// the values for the placeholders: project.<key> for every value in [package],
+    /// and whatever is in [resources.properties]
+    pub properties: BTreeMap<String, String>,
+}
+
+/// The [test] table
+#[derive(Debug, Clone, Default)]
+pub struct TestConfig {
+    /// the number of JVMs the test classes are divided over
+    pub forks: Forks,
+    /// a test class that runs longer than this is killed, together with its JVM
+    pub timeout: Option<Duration>,
+}
+
+/// The [run] table, for `jargo run`
+#[derive(Debug, Clone, Default)]
+pub struct RunConfig {
+    /// options for the JVM, like -Xmx1g, before the main class
+    pub jvm_args: Vec<String>,
+    /// the environment variables in [run.env]
+    pub env: BTreeMap<String, String>,
+}
+
+/// A number of JVMs, either fixed or per CPU core (like "1C")
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Forks {
+    Count(usize),
+    PerCore(f64),
+}
+
+impl Default for Forks {
+    fn default() -> Self {
+        Forks::Count(1)
+    }
+}
+
+impl Forks {
+    pub fn parse(forks: &str) -> Result<Self, Error> {
+        let invalid = || anyhow!("invalid number of forks {}, use eg. 4 or \"1C\"", forks);
+        match forks.strip_suffix('C') {
+            Some(per_core) => {
+                let per_core: f64 = per_core.parse().map_err(|_| invalid())?;
+                if per_core <= 0.0 {
+                    return Err(invalid());
+                }
+                Ok(Forks::PerCore(per_core))
+            }
+            None => match forks.parse() {
+                Ok(0) | Err(_) => Err(invalid()),
+                Ok(count) => Ok(Forks::Count(count)),
+            },
+        }
+    }
+
+    /// the actual number of JVMs, at least 1
+    pub fn count(&self) -> usize {
+        match self {
+            Forks::Count(count) => *count,
+            Forks::PerCore(per_core) => {
+                let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+                (per_core * cores as f64).round() as usize
+            }
+        }
+        .max(1)
+    }
+}
+
+/// A dependency on another jargo project, that is built before this one
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathDependency {
+    pub group: String,
+    pub name: String,
+    /// the directory of the other project
+    pub path: PathBuf,
 }
 
 /// The identifier for any released bundle (jar, war etc) like in maven
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Artifact {
     pub group: String,
     pub name: String,
-    pub version: String,
+    /// as written, so a range until it is resolved
+    pub version: Version,
     pub path: String,
 }
 
@@ -31,102 +339,622 @@ impl Artifact {
         Self {
             group: group.into(),
             name: name.into(),
-            version: version.into(),
+            version: Version::new(version),
             path: format!("{}/{}/{}", group.replace(".", "/"), name, version),
         }
     }
 
     pub fn is_snapshot(&self) -> bool {
-        self.version.ends_with("-SNAPSHOT")
-    }
-}
-
-/// Convert from XML view
-impl From<Dependency> for Artifact {
-    fn from(value: Dependency) -> Self {
-        Self::new(
-            &value.group_id.value,
-            &value.artifact_id.value,
-            &value.version.unwrap().value,
-        )
+        self.version.is_snapshot()
     }
 }
 
 impl Artifact {
     /// Convert from TOML view
-    pub fn from_table_entry(name_group: &str, version: String) -> Result<Self, Error> {
+    pub fn from_table_entry(name_group: &str, version: &Value) -> Result<Self, Error> {
         let name_group_split: Vec<&str> = name_group.split(":").collect();
         if 2 != name_group_split.len() {
-            return Err(anyhow!("dependency {} not well formatted", name_group));
+            return Err(anyhow!(
+                "dependency {} not well formatted, use \"group:name\"",
+                name_group
+            ));
         }
-        let group = name_group_split[0].into();
-        let name = name_group_split[1].into();
+        let group = name_group_split[0];
+        let name = name_group_split[1];
+        let version = version.as_str().ok_or_else(|| {
+            anyhow!(
+                "the version of {} should be a string, like \"1.0\"",
+                name_group
+            )
+        })?;
 
-        Ok(Self::new(
-            group,
-            name,
-            version[1..version.len() - 1].to_owned().as_str(),
-        ))
+        Ok(Self::new(group, name, version))
     }
 }
 
-/// loads the project from the TOML file
+/// Loads the project from the TOML file.
+/// Anything wrong in it is a [JargoError::Manifest].
 pub fn load_project(jargo_file: Option<&str>) -> Result<Project, Error> {
     let jargo = Path::new(jargo_file.unwrap_or("./Jargo.toml"));
+    let project_table = manifest::read(jargo)?;
+    let mut project =
+        parse_project(jargo, &project_table).map_err(|e| match e.downcast::<JargoError>() {
+            Ok(e) => e,
+            Err(e) => JargoError::Manifest {
+                file: jargo.to_path_buf(),
+                key: None,
+                position: None,
+                message: e.to_string(),
+            },
+        })?;
+    // not a manifest error, the JDK may just not be installed
+    project.jdk = project.java.as_ref().map(toolchain::select).transpose()?;
+    Ok(project)
+}
 
-    let project_table = fs::read_to_string(jargo)?.parse::<Table>()?;
-    let package = project_table.get("package").expect("package info missing");
+fn parse_project(jargo: &Path, project_table: &Table) -> Result<Project, Error> {
+    let manifest_error = |key: &str, message: &str| JargoError::Manifest {
+        file: jargo.to_path_buf(),
+        key: Some(key.into()),
+        position: None,
+        message: message.into(),
+    };
+    let package = project_table
+        .get("package")
+        .ok_or_else(|| manifest_error("package", "[package] is missing"))?;
+    let required = |key: &str| -> Result<String, JargoError> {
+        let value = package
+            .get(key)
+            .ok_or_else(|| manifest_error(&format!("package.{}", key), "is missing"))?;
+        value
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| manifest_error(&format!("package.{}", key), "should be a string"))
+    };
+    let (group, name, version) = (required("group")?, required("name")?, required("version")?);
 
-    let repositories = repositories(project_table.get("repositories"))?;
-    let main_dependencies = dependencies(project_table.get("dependencies"))?;
-    let test_dependencies = dependencies(project_table.get("test-dependencies"))?;
+    let project_root: String = jargo
+        .parent()
+        .and_then(Path::to_str)
+        .ok_or_else(|| anyhow!("the directory of {} is not usable", jargo.display()))?
+        .into();
+    let project_dir = fs::canonicalize(if project_root.is_empty() {
+        "."
+    } else {
+        &project_root
+    })?;
+    let workspace = WorkspaceConfig::find(&project_dir)?;
+
+    let resolver = |key: &str| {
+        project_table
+            .get("resolver")
+            .and_then(|r| r.get(key))
+            .and_then(Value::as_str)
+    };
+    let checksums = match resolver("checksums") {
+        Some(policy) => ChecksumPolicy::parse(policy)?,
+        None => ChecksumPolicy::default(),
+    };
+    let snapshots = match resolver("snapshots") {
+        Some(policy) => UpdatePolicy::parse(policy)?,
+        None => UpdatePolicy::default(),
+    };
+    let releases = match resolver("releases") {
+        Some(policy) => UpdatePolicy::parse(policy)?,
+        None => UpdatePolicy::default(),
+    };
+    let repositories = repositories(
+        project_table.get("repositories"),
+        checksums,
+        snapshots,
+        releases,
+    )?;
+    let dependency_table = |key: &str| {
+        dependencies(
+            project_table.get(key),
+            key,
+            &project_dir,
+            workspace.as_ref(),
+        )
+    };
+    let (main_dependencies, path_dependencies) = dependency_table("dependencies")?;
+    let (test_dependencies, test_path_dependencies) = dependency_table("test-dependencies")?;
+    let (annotation_processors, processor_paths) = dependency_table("annotation-processors")?;
+    if !processor_paths.is_empty() {
+        return Err(anyhow!(
+            "annotation-processors can't be path dependencies, they are resolved from a repository"
+        ));
+    }
+    let target_dir = match &workspace {
+        Some(workspace) => workspace.target_dir(&project_dir),
+        None => Path::new(&project_root).join("target"),
+    };
+
+    let kind = match package.get("kind").and_then(Value::as_str) {
+        Some(kind) => PackageKind::parse(kind)?,
+        None => PackageKind::Jar,
+    };
+    let main_class = package
+        .get("main-class")
+        .and_then(Value::as_str)
+        .map(String::from);
+    let relocations = relocations(project_table.get("shade"))?;
+    let test = test_config(project_table.get("test"))?;
+    let run = run_config(project_table.get("run"))?;
+    let resources = resources(project_table.get("resources"), package)?;
+    let compile = compile_options(project_table.get("compile"), "compile")?;
+    let test_compile = compile.with(&compile_options(
+        project_table.get("compile").and_then(|c| c.get("test")),
+        "compile.test",
+    )?);
+    let java = java_request(package, project_table.get("toolchain"))?;
 
     Ok(Project {
-        group: strip_first_last(package.get("group").unwrap().to_string()),
-        name: strip_first_last(package.get("name").unwrap().to_string()),
-        version: strip_first_last(package.get("version").unwrap().to_string()),
+        group,
+        name,
+        version,
         repositories,
+        checksums,
         main_dependencies,
         test_dependencies,
-        project_root: jargo
-            .parent()
-            .map(Path::to_str)
-            .unwrap()
-            .expect(&format!("projectroot {:?} not usable", jargo))
-            .into(),
+        path_dependencies,
+        test_path_dependencies,
+        annotation_processors,
+        kind,
+        main_class,
+        relocations,
+        test,
+        run,
+        resources,
+        compile,
+        test_compile,
+        java,
+        jdk: None,
+        project_root,
+        target_dir: target_dir.to_string_lossy().into(),
     })
 }
 
-fn repositories(table: Option<&Value>) -> Result<Vec<String>, Error> {
-    let mut repositories = vec!["https://repo.maven.apache.org/maven2".to_owned()];
-        if let Some(Some(table)) = table.map(|t|t.as_table()) {
-            for repo in table {
-                let repo_details = repo.1.clone();
-                if let Value::Table(repo_details) = repo_details {
-                    if let Some(Value::String(url)) = repo_details.get("url") {
-                        repositories.push(url.into());
-                    }
+/// maven central, followed by the [repositories], that use the checksum and update policies
+/// of [resolver] unless they have their own
+fn repositories(
+    table: Option<&Value>,
+    checksums: ChecksumPolicy,
+    snapshots: UpdatePolicy,
+    releases: UpdatePolicy,
+) -> Result<Vec<Repository>, Error> {
+    let mut repositories = vec![Repository {
+        name: "central".into(),
+        url: "https://repo.maven.apache.org/maven2".to_owned(),
+        checksums,
+        snapshots,
+        releases,
+    }];
+    if let Some(Some(table)) = table.map(|t| t.as_table()) {
+        for repo in table {
+            let repo_details = repo.1.clone();
+            if let Value::Table(repo_details) = repo_details {
+                if let Some(Value::String(url)) = repo_details.get("url") {
+                    let checksums = match repo_details.get("checksums").and_then(Value::as_str) {
+                        Some(policy) => ChecksumPolicy::parse(policy)?,
+                        None => checksums,
+                    };
+                    let snapshots = match repo_details.get("snapshots").and_then(Value::as_str) {
+                        Some(policy) => UpdatePolicy::parse(policy)?,
+                        None => snapshots,
+                    };
+                    let releases = match repo_details.get("releases").and_then(Value::as_str) {
+                        Some(policy) => UpdatePolicy::parse(policy)?,
+                        None => releases,
+                    };
+                    repositories.push(Repository {
+                        name: repo.0.clone(),
+                        url: url.into(),
+                        checksums,
+                        snapshots,
+                        releases,
+                    });
                 }
+            }
         }
     }
     Ok(repositories)
 }
 
+/// the [shade] relocate table, from package name to new package name
+fn relocations(shade: Option<&Value>) -> Result<Vec<Relocation>, Error> {
+    let mut relocations = vec![];
+    if let Some(relocate) = shade.and_then(|s| s.get("relocate")) {
+        let relocate = relocate
+            .as_table()
+            .ok_or_else(|| anyhow!("shade.relocate should be a table"))?;
+        for (from, to) in relocate {
+            let to = to
+                .as_str()
+                .ok_or_else(|| anyhow!("relocation for {} should be a package name", from))?;
+            relocations.push(Relocation {
+                from: from.clone(),
+                to: to.into(),
+            });
+        }
+    }
+    Ok(relocations)
+}
+
+/// the [resources] table, with the placeholder values from [package] and [resources.properties]
+fn resources(table: Option<&Value>, package: &Value) -> Result<Resources, Error> {
+    let mut resources = Resources::default();
+    if let Some(package) = package.as_table() {
+        for (key, value) in package {
+            if let Some(value) = value.as_str() {
+                resources
+                    .properties
+                    .insert(format!("project.{}", key), value.into());
+            }
+        }
+    }
+    if let Some(filter) = table.and_then(|t| t.get("filter")) {
+        let globs = filter
+            .as_array()
+            .ok_or_else(|| anyhow!("resources.filter should be a list of globs"))?;
+        for glob in globs {
+            let glob = glob
+                .as_str()
+                .ok_or_else(|| anyhow!("resources.filter should be a list of globs"))?;
+            resources.filter.push(glob.into());
+        }
+    }
+    if let Some(properties) = table.and_then(|t| t.get("properties")) {
+        let properties = properties
+            .as_table()
+            .ok_or_else(|| anyhow!("resources.properties should be a table"))?;
+        for (key, value) in properties {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+            resources.properties.insert(key.clone(), value);
+        }
+    }
+    Ok(resources)
+}
+
+/// the javac options in [compile] or [compile.test]
+fn compile_options(table: Option<&Value>, name: &str) -> Result<CompileOptions, Error> {
+    let mut options = CompileOptions::default();
+    let Some(table) = table else {
+        return Ok(options);
+    };
+    let invalid = |key: &str, expected: &str| anyhow!("{}.{} should be {}", name, key, expected);
+    let string = |key: &str| -> Result<Option<String>, Error> {
+        match table.get(key) {
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(Value::Integer(value)) => Ok(Some(value.to_string())),
+            Some(_) => Err(invalid(key, "a string")),
+            None => Ok(None),
+        }
+    };
+    let boolean = |key: &str| -> Result<Option<bool>, Error> {
+        match table.get(key) {
+            Some(value) => Ok(Some(
+                value
+                    .as_bool()
+                    .ok_or_else(|| invalid(key, "true or false"))?,
+            )),
+            None => Ok(None),
+        }
+    };
+    let strings = |key: &str, value: &Value| -> Result<Vec<String>, Error> {
+        value
+            .as_array()
+            .ok_or_else(|| invalid(key, "a list of strings"))?
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(String::from)
+                    .ok_or_else(|| invalid(key, "a list of strings"))
+            })
+            .collect()
+    };
+
+    options.release = string("release")?;
+    options.encoding = string("encoding")?;
+    options.parameters = boolean("parameters")?;
+    options.warnings_as_errors = boolean("warnings-as-errors")?;
+    // lint = true, lint = "all,-serial" or lint = ["all", "-serial"]
+    options.lint = match table.get("lint") {
+        Some(Value::Boolean(true)) => Some(vec![]),
+        Some(Value::Boolean(false)) => Some(vec!["none".to_owned()]),
+        Some(Value::String(keys)) => Some(keys.split(',').map(|k| k.trim().to_owned()).collect()),
+        Some(keys) => Some(strings("lint", keys)?),
+        None => None,
+    };
+    // debug = true, false, "all", "none" or "lines,vars,source"
+    options.debug = match table.get("debug") {
+        Some(Value::Boolean(true)) => Some("all".to_owned()),
+        Some(Value::Boolean(false)) => Some("none".to_owned()),
+        Some(Value::String(debug)) => Some(debug.clone()),
+        Some(_) => {
+            return Err(invalid(
+                "debug",
+                "true, false or a list like \"lines,source\"",
+            ))
+        }
+        None => None,
+    };
+    if let Some(processor_options) = table.get("processor-options") {
+        let processor_options = processor_options
+            .as_table()
+            .ok_or_else(|| invalid("processor-options", "a table"))?;
+        options.processor_options = Some(
+            processor_options
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(value) => value.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        );
+    }
+    options.args = table
+        .get("args")
+        .map(|args| strings("args", args))
+        .transpose()?;
+    Ok(options)
+}
+
+/// java = "21" in [toolchain] or [package], and optionally vendor = "temurin" in [toolchain]
+fn java_request(package: &Value, toolchain: Option<&Value>) -> Result<Option<JavaRequest>, Error> {
+    let version = toolchain
+        .and_then(|t| t.get("java"))
+        .or_else(|| package.get("java"));
+    let version = match version {
+        Some(Value::String(version)) => version.clone(),
+        Some(Value::Integer(version)) => version.to_string(),
+        Some(_) => return Err(anyhow!("java should be a version, like \"21\"")),
+        None => return Ok(None),
+    };
+    let vendor = toolchain
+        .and_then(|t| t.get("vendor"))
+        .and_then(Value::as_str)
+        .map(String::from);
+    Ok(Some(JavaRequest { version, vendor }))
+}
+
+/// the [test] table, forks is a number or a string like "1C", timeout is in seconds
+fn test_config(test: Option<&Value>) -> Result<TestConfig, Error> {
+    let mut config = TestConfig::default();
+    if let Some(forks) = test.and_then(|t| t.get("forks")) {
+        config.forks = match forks {
+            Value::Integer(count) => Forks::parse(&count.to_string())?,
+            Value::String(forks) => Forks::parse(forks)?,
+            _ => {
+                return Err(anyhow!(
+                    "test.forks should be a number or a string like \"1C\""
+                ))
+            }
+        };
+    }
+    if let Some(timeout) = test.and_then(|t| t.get("timeout")) {
+        let seconds = timeout
+            .as_integer()
+            .filter(|s| *s > 0)
+            .ok_or_else(|| anyhow!("test.timeout should be a number of seconds"))?;
+        config.timeout = Some(Duration::from_secs(seconds as u64));
+    }
+    Ok(config)
+}
+
+fn run_config(run: Option<&Value>) -> Result<RunConfig, Error> {
+    let mut config = RunConfig::default();
+    if let Some(jvm_args) = run.and_then(|r| r.get("jvm-args")) {
+        let invalid = || anyhow!("run.jvm-args should be a list of strings");
+        for arg in jvm_args.as_array().ok_or_else(invalid)? {
+            config
+                .jvm_args
+                .push(arg.as_str().ok_or_else(invalid)?.to_owned());
+        }
+    }
+    if let Some(env) = run.and_then(|r| r.get("env")) {
+        let env = env
+            .as_table()
+            .ok_or_else(|| anyhow!("run.env should be a table"))?;
+        for (key, value) in env {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+            config.env.insert(key.clone(), value);
+        }
+    }
+    Ok(config)
+}
+
 /// convert dependencies from the TOML view
-fn dependencies(table: Option<&Value>) -> Result<Vec<Artifact>, Error> {
+///
+/// A dependency is a version, or a table with the version, with the `path` of another project
+/// relative to this one, or with `workspace = true` to take it from [workspace.dependencies]
+fn dependencies(
+    table: Option<&Value>,
+    name: &str,
+    project_dir: &Path,
+    workspace: Option<&WorkspaceConfig>,
+) -> Result<(Vec<Artifact>, Vec<PathDependency>), Error> {
     let mut dependencies = vec![];
+    let mut path_dependencies = vec![];
     if let Some(table) = table {
         let table = table.as_table();
         if let Some(table) = table {
             for dep in table {
-                dependencies.push(Artifact::from_table_entry(dep.0, dep.1.to_string())?);
+                let inherited = dep.1.get("workspace").and_then(Value::as_bool) == Some(true);
+                let (dep, project_dir) = if inherited {
+                    let workspace = workspace.ok_or_else(|| {
+                        anyhow!(
+                            "{}.{} has workspace = true, but the project is not in a workspace",
+                            name,
+                            dep.0
+                        )
+                    })?;
+                    let inherited = workspace
+                        .dependencies
+                        .get(dep.0)
+                        .ok_or_else(|| anyhow!("{} is not in [workspace.dependencies]", dep.0))?;
+                    ((dep.0, inherited), workspace.root.as_path())
+                } else {
+                    (dep, project_dir)
+                };
+                match dep.1 {
+                    Value::String(_) => {
+                        dependencies.push(Artifact::from_table_entry(dep.0, dep.1)?)
+                    }
+                    Value::Table(details) => {
+                        if let Some(path) = details.get("path").and_then(Value::as_str) {
+                            path_dependencies.push(path_dependency(dep.0, project_dir, path)?);
+                        } else if let Some(version) = details.get("version") {
+                            dependencies.push(Artifact::from_table_entry(dep.0, version)?);
+                        } else {
+                            return Err(anyhow!(
+                                "{}.{} should have a version, a path or workspace = true",
+                                name,
+                                dep.0
+                            ));
+                        }
+                    }
+                    _ => return Err(anyhow!("{}.{} should be a version", name, dep.0)),
+                }
             }
         }
     }
-    Ok(dependencies)
+    Ok((dependencies, path_dependencies))
+}
+
+fn path_dependency(
+    name_group: &str,
+    project_dir: &Path,
+    path: &str,
+) -> Result<PathDependency, Error> {
+    let (group, name) = name_group
+        .split_once(':')
+        .ok_or_else(|| anyhow!("dependency {} not well formatted", name_group))?;
+    let path = fs::canonicalize(project_dir.join(path))
+        .map_err(|_| anyhow!("path {} of {} doesn't exist", path, name_group))?;
+    if !path.join("Jargo.toml").exists() {
+        return Err(anyhow!(
+            "path {} of {} has no Jargo.toml",
+            path.display(),
+            name_group
+        ));
+    }
+    Ok(PathDependency {
+        group: group.into(),
+        name: name.into(),
+        path,
+    })
 }
 
-/// Because strings in the toml are surrounded by double quotes
-fn strip_first_last(text: String) -> String {
-    text[1..text.len() - 1].into()
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use toml::Table;
+
+    use crate::checksum::ChecksumPolicy;
+    use crate::project::{compile_options, repositories, UpdatePolicy};
+
+    #[test]
+    fn compile_options_with_test_overrides() {
+        let table: Table = r#"
+            release = 17
+            lint = "all,-serial"
+            debug = false
+            args = ["-implicit:none"]
+            processor-options = { "mapstruct.defaultComponentModel" = "spring", verbose = true }
+            [test]
+            release = "21"
+            parameters = true
+            "#
+        .parse()
+        .unwrap();
+        let table = toml::Value::Table(table);
+        let main = compile_options(Some(&table), "compile").unwrap();
+        assert_eq!(
+            main.javac_args(),
+            vec![
+                "--release",
+                "17",
+                "-Xlint:all,-serial",
+                "-g:none",
+                "-Amapstruct.defaultComponentModel=spring",
+                "-Averbose=true",
+                "-implicit:none"
+            ]
+        );
+        let test = main.with(&compile_options(table.get("test"), "compile.test").unwrap());
+        assert_eq!(
+            test.javac_args(),
+            vec![
+                "--release",
+                "21",
+                "-parameters",
+                "-Xlint:all,-serial",
+                "-g:none",
+                "-Amapstruct.defaultComponentModel=spring",
+                "-Averbose=true",
+                "-implicit:none"
+            ]
+        );
+    }
+
+    #[test]
+    fn update_policies() {
+        assert_eq!(
+            UpdatePolicy::parse("interval:90").unwrap(),
+            UpdatePolicy::Interval(90)
+        );
+        assert!(UpdatePolicy::parse("hourly").is_err());
+
+        // 2024-01-01 23:00 and 2024-01-02 01:00 UTC
+        let evening = UNIX_EPOCH + Duration::from_secs(1704150000);
+        let night = evening + Duration::from_secs(2 * 60 * 60);
+        assert!(UpdatePolicy::Daily.is_due(Some(evening), night));
+        assert!(!UpdatePolicy::Daily.is_due(Some(night), night + Duration::from_secs(60)));
+        assert!(UpdatePolicy::Interval(120).is_due(Some(evening), night));
+        assert!(!UpdatePolicy::Interval(121).is_due(Some(evening), night));
+        assert!(!UpdatePolicy::Never.is_due(Some(evening), night));
+        assert!(UpdatePolicy::Never.is_due(None, night));
+        assert!(UpdatePolicy::Always.is_due(Some(night), night));
+    }
+
+    #[test]
+    fn repository_policies() {
+        let table: toml::Value = r#"
+            snapshots = {url = "https://repo.example.com/snapshots", releases = "never"}
+            releases = {url = "https://repo.example.com/releases", snapshots = "always"}
+        "#
+        .parse::<Table>()
+        .unwrap()
+        .into();
+        let repositories = repositories(
+            Some(&table),
+            ChecksumPolicy::Fail,
+            UpdatePolicy::Daily,
+            UpdatePolicy::Interval(60),
+        )
+        .unwrap();
+        let policies: Vec<_> = repositories
+            .iter()
+            .map(|r| (r.name.as_str(), r.snapshots, r.releases))
+            .collect();
+        assert_eq!(
+            policies,
+            [
+                ("central", UpdatePolicy::Daily, UpdatePolicy::Interval(60)),
+                ("releases", UpdatePolicy::Always, UpdatePolicy::Interval(60)),
+                ("snapshots", UpdatePolicy::Daily, UpdatePolicy::Never),
+            ]
+        );
+    }
 }
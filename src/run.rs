@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Error};
+use colored::Colorize;
+
+use crate::classfile::ClassFile;
+use crate::compile::{write_argfile, TARGET_MAIN};
+use crate::deploader::Classpath;
+use crate::package::dir_entries;
+use crate::project::Project;
+
+/// Runs the main class with java and returns the exit code of the program, see [command]
+pub fn run(
+    project: &Project,
+    classpath: &Classpath,
+    main_class: Option<&str>,
+    args: &[String],
+) -> Result<i32, Error> {
+    let status = command(project, classpath, main_class, args)?
+        .status()
+        .map_err(|e| anyhow!("failed to execute java: {}", e))?;
+    // killed by a signal, like the shell reports it
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return Ok(128 + signal);
+    }
+    Ok(status.code().unwrap_or(1))
+}
+
+/// The java command for the main class, with target/classes and the runtime dependencies on the
+/// classpath, from the project root. The main class is `main_class`, or else main-class in [package],
+/// or else the only class with a main method.
+pub fn command(
+    project: &Project,
+    classpath: &Classpath,
+    main_class: Option<&str>,
+    args: &[String],
+) -> Result<Command, Error> {
+    let root = PathBuf::from(&project.project_root);
+    let classes = PathBuf::from(&project.target_dir).join(TARGET_MAIN);
+    let main_class = match main_class.or(project.main_class.as_deref()) {
+        Some(main_class) => main_class.to_owned(),
+        None => find_main_class(&classes)?,
+    };
+    println!("{} {}", "Running".green(), main_class);
+
+    let mut runtime = vec![classes.to_string_lossy().to_string()];
+    runtime.extend(classpath.runtime().iter().cloned());
+    let mut java_args = project.run.jvm_args.clone();
+    java_args.push("-classpath".into());
+    java_args.push(std::env::join_paths(runtime)?.to_string_lossy().to_string());
+    java_args.push(main_class);
+    // the classpath can be longer than the command line allows
+    let argfile = PathBuf::from(&project.target_dir).join("jargo/run.args");
+    if let Some(parent) = argfile.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_argfile(&argfile, &java_args)?;
+
+    let mut java = project.java();
+    java.arg(format!("@{}", argfile.to_string_lossy()))
+        .args(args)
+        .envs(&project.run.env)
+        .current_dir(&root);
+    Ok(java)
+}
+
+/// the class with a `public static void main`, when there is exactly one
+fn find_main_class(classes: &Path) -> Result<String, Error> {
+    let mut main_classes = vec![];
+    for (name, file) in dir_entries(classes)? {
+        if !name.ends_with(".class") {
+            continue;
+        }
+        let class = ClassFile::parse(&fs::read(&file)?)?;
+        if class.has_main_method() {
+            if let Some(this_class) = class.this_class() {
+                main_classes.push(this_class.replace('/', "."));
+            }
+        }
+    }
+    match main_classes.len() {
+        0 => Err(anyhow!(
+            "no class with a main method found, set main-class in [package] or use --main"
+        )),
+        1 => Ok(main_classes.remove(0)),
+        _ => Err(anyhow!(
+            "more than one class with a main method: {}. Set main-class in [package] or use --main",
+            main_classes.join(", ")
+        )),
+    }
+}
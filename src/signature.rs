@@ -0,0 +1,196 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Error};
+use colored::Colorize;
+
+use crate::error::JargoError;
+
+/// [signatures] in ~/jargo/config.toml
+///
+/// ```toml
+/// [signatures]
+/// keyring = "keys.gpg"
+/// policy = "warn"
+/// key-changes = "fail"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureConfig {
+    /// the public keys to verify with, made with `gpg --export` (not armored), relative to ~/jargo
+    pub keyring: PathBuf,
+    /// what to do with an artifact without a signature, or with a signature that can't be verified
+    pub policy: SignaturePolicy,
+    /// what to do when a group is signed with another key than the one recorded in Jargo.lock
+    pub key_changes: SignaturePolicy,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SignaturePolicy {
+    /// stop with an error
+    #[default]
+    Fail,
+    /// show a warning and continue
+    Warn,
+}
+
+impl SignaturePolicy {
+    pub(crate) fn parse(policy: &str) -> Result<Self, Error> {
+        match policy {
+            "fail" => Ok(SignaturePolicy::Fail),
+            "warn" => Ok(SignaturePolicy::Warn),
+            _ => Err(anyhow!(
+                "unknown signature policy {}, use 'fail' or 'warn'",
+                policy
+            )),
+        }
+    }
+
+    /// an error with the fail policy, a warning with the warn policy
+    pub(crate) fn apply(&self, error: JargoError) -> Result<(), Error> {
+        match self {
+            SignaturePolicy::Fail => Err(error.into()),
+            SignaturePolicy::Warn => {
+                println!("{}: {}", "warning".yellow().bold(), error);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// the outcome of checking a signature
+#[derive(Debug, PartialEq)]
+pub enum Verification {
+    /// a good signature, with the fingerprint of the (primary) key that made it
+    Signed(String),
+    /// why it can't be trusted
+    Invalid(String),
+}
+
+/// Verifies a detached signature (.asc) of the contents with gpgv, against the keys in the keyring only
+pub fn verify(contents: &[u8], signature: &Path, keyring: &Path) -> Result<Verification, Error> {
+    let mut gpgv = Command::new("gpgv")
+        // so that the trustedkeys of the user are not used
+        .arg("--homedir")
+        .arg(keyring.parent().unwrap_or(Path::new(".")))
+        .arg("--keyring")
+        .arg(keyring)
+        .args(["--status-fd", "1"])
+        .arg(signature)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("failed to execute gpgv: {}", e))?;
+    if let Some(mut stdin) = gpgv.stdin.take() {
+        stdin.write_all(contents)?;
+    }
+    let output = gpgv.wait_with_output()?;
+    Ok(parse_status(
+        &String::from_utf8_lossy(&output.stdout),
+        output.status.success(),
+    ))
+}
+
+/// reads the --status-fd output of gpgv
+fn parse_status(status: &str, success: bool) -> Verification {
+    let mut good = false;
+    let mut fingerprint = None;
+    for line in status.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.get(1).copied() {
+            Some("GOODSIG") => good = true,
+            // the last field is the fingerprint of the primary key, also for a signing subkey
+            Some("VALIDSIG") => fingerprint = fields.last().map(|f| f.to_string()),
+            Some("BADSIG") => return Verification::Invalid("the signature doesn't match".into()),
+            // 9 is the code for a missing key
+            Some("ERRSIG") if fields.get(7) == Some(&"9") => {
+                return Verification::Invalid(format!(
+                    "signed with key {}, that is not in the keyring",
+                    fields.get(2).unwrap_or(&"?")
+                ))
+            }
+            Some("EXPKEYSIG") | Some("REVKEYSIG") => {
+                return Verification::Invalid(format!(
+                    "signed with key {}, that is {}",
+                    fields.get(2).unwrap_or(&"?"),
+                    if fields[1] == "EXPKEYSIG" {
+                        "expired"
+                    } else {
+                        "revoked"
+                    }
+                ))
+            }
+            _ => {}
+        }
+    }
+    match fingerprint {
+        Some(fingerprint) if good && success => Verification::Signed(fingerprint),
+        _ => Verification::Invalid("gpgv could not verify the signature".into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use std::process::Command;
+
+    use crate::signature::{parse_status, verify, Verification};
+
+    #[test]
+    fn gpgv_status() {
+        let status = "[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 0123456789ABCDEF0123456789ABCDEF01234567 0
+[GNUPG:] SIG_ID abc 2024-01-01 1704067200
+[GNUPG:] GOODSIG 89ABCDEF01234567 Example <dev@example.com>
+[GNUPG:] VALIDSIG FEDCBA9876543210FEDCBA9876543210FEDCBA98 2024-01-01 1704067200 0 4 0 1 10 00 0123456789ABCDEF0123456789ABCDEF01234567
+";
+        assert_eq!(
+            parse_status(status, true),
+            Verification::Signed("0123456789ABCDEF0123456789ABCDEF01234567".into())
+        );
+
+        let status = "[GNUPG:] NEWSIG
+[GNUPG:] ERRSIG 89ABCDEF01234567 1 10 00 1704067200 9 -
+[GNUPG:] NO_PUBKEY 89ABCDEF01234567
+";
+        assert_eq!(
+            parse_status(status, false),
+            Verification::Invalid(
+                "signed with key 89ABCDEF01234567, that is not in the keyring".into()
+            )
+        );
+
+        let status = "[GNUPG:] BADSIG 89ABCDEF01234567 Example <dev@example.com>\n";
+        assert_eq!(
+            parse_status(status, false),
+            Verification::Invalid("the signature doesn't match".into())
+        );
+    }
+
+    /// with the keys in tests/signatures, skipped when gpgv is not installed
+    #[test]
+    fn verify_with_fixture_keys() {
+        if Command::new("gpgv").arg("--version").output().is_err() {
+            return;
+        }
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/signatures");
+        let keyring = fixtures.join("keys.gpg");
+        let contents = std::fs::read(fixtures.join("artifact.txt")).unwrap();
+        assert_eq!(
+            verify(&contents, &fixtures.join("artifact.txt.asc"), &keyring).unwrap(),
+            Verification::Signed("4B59B163A58648F14576A48BABD82462F52D130B".into())
+        );
+        assert_eq!(
+            verify(b"tampered", &fixtures.join("artifact.txt.asc"), &keyring).unwrap(),
+            Verification::Invalid("the signature doesn't match".into())
+        );
+        assert_eq!(
+            verify(&contents, &fixtures.join("other.txt.asc"), &keyring).unwrap(),
+            Verification::Invalid(
+                "signed with key 2F30B92ECD739FAE, that is not in the keyring".into()
+            )
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Error;
+
+use crate::test::{TestResult, CLASS_ERROR};
+
+/// where the tests that failed in the previous run are kept, relative to the target directory
+const LAST_FAILED: &str = "jargo/last-failed";
+
+/// Which tests to run
+///
+/// The pattern is a glob on the (simple or fully qualified) class name, optionally followed by
+/// `#` or `::` and a glob on the method name, eg. `*ServiceTest` or `UserTest#create*`.
+/// Tags are JUnit 5 tags, JUnit 4 categories or TestNG groups.
+#[derive(Debug, Default, Clone)]
+pub struct TestFilter {
+    pub pattern: Option<String>,
+    pub tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    /// only run the tests that failed in the previous run
+    pub failed: bool,
+}
+
+/// a test class, and optionally a glob for the methods in it to run
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
+pub(super) struct Selector {
+    pub(super) class: String,
+    pub(super) method: Option<String>,
+}
+
+impl TestFilter {
+    /// selects from the discovered test classes
+    pub(super) fn select(
+        &self,
+        target: &Path,
+        test_classes: &[String],
+    ) -> Result<Vec<Selector>, Error> {
+        let (class_pattern, method_pattern) = match &self.pattern {
+            Some(pattern) => match pattern.split_once('#').or(pattern.split_once("::")) {
+                Some((class, method)) => (class, Some(method.to_owned())),
+                None => (pattern.as_str(), None),
+            },
+            None => ("*", None),
+        };
+        let class_matches = |class: &str| {
+            let simple_name = class.rsplit('.').next().unwrap_or(class);
+            glob_matches(class_pattern, class) || glob_matches(class_pattern, simple_name)
+        };
+
+        let mut selectors = BTreeSet::new();
+        if self.failed {
+            for id in read_last_failed(target)? {
+                // a class that failed outside of its test methods is run as a whole
+                let (class, method) = match id.split_once("::") {
+                    // parameterized tests are run again for all parameters
+                    Some((class, method)) => (class, method.split('[').next()),
+                    None => (id.as_str(), None),
+                };
+                if test_classes.iter().any(|c| c == class) && class_matches(class) {
+                    selectors.insert(Selector {
+                        class: class.into(),
+                        method: method.map(String::from),
+                    });
+                }
+            }
+            let whole: Vec<String> = selectors
+                .iter()
+                .filter(|s| s.method.is_none())
+                .map(|s| s.class.clone())
+                .collect();
+            selectors.retain(|s| s.method.is_none() || !whole.contains(&s.class));
+        } else {
+            for class in test_classes.iter().filter(|c| class_matches(c)) {
+                selectors.insert(Selector {
+                    class: class.clone(),
+                    method: method_pattern.clone(),
+                });
+            }
+        }
+        Ok(selectors.into_iter().collect())
+    }
+
+    /// the arguments for the runner in the test JVM, see Selection.java
+    pub(super) fn runner_args(&self, selectors: &[Selector]) -> Vec<String> {
+        let mut args = vec![];
+        for tag in &self.tags {
+            args.push("--include-tag".into());
+            args.push(tag.clone());
+        }
+        for tag in &self.exclude_tags {
+            args.push("--exclude-tag".into());
+            args.push(tag.clone());
+        }
+        for selector in selectors {
+            match &selector.method {
+                Some(method) => args.push(format!("{}#{}", selector.class, method)),
+                None => args.push(selector.class.clone()),
+            }
+        }
+        args
+    }
+}
+
+/// Keeps the failed tests for `jargo test --failed`.
+/// Tests that ran again are replaced by their new result, the others are kept,
+/// so that running a subset doesn't forget the other failures.
+pub(super) fn store_failed(target: &Path, results: &[TestResult]) -> Result<(), Error> {
+    let mut failed: BTreeSet<String> = read_last_failed(target)?.into_iter().collect();
+    for result in results {
+        failed.remove(&result.id());
+        failed.remove(&result.class);
+    }
+    for result in results.iter().filter(|r| r.is_failure()) {
+        if result.name == CLASS_ERROR {
+            failed.insert(result.class.clone());
+        } else {
+            failed.insert(result.id());
+        }
+    }
+    let path = target.join(LAST_FAILED);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut contents = failed.into_iter().collect::<Vec<_>>().join("\n");
+    contents.push('\n');
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+fn read_last_failed(target: &Path) -> Result<Vec<String>, Error> {
+    let path = target.join(LAST_FAILED);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// `*` matches any number of characters, `?` exactly one
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // position of the last * in the pattern and the text position it matched up to
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::test::filter::{glob_matches, store_failed, Selector, TestFilter};
+    use crate::test::{Status, TestResult};
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("*", "anything"));
+        assert!(glob_matches("*Test", "UserServiceTest"));
+        assert!(glob_matches("User*Test", "UserServiceTest"));
+        assert!(glob_matches("get?", "getA"));
+        assert!(glob_matches("*a*b", "xaybab"));
+        assert!(!glob_matches("*Test", "UserServiceTests"));
+        assert!(!glob_matches("get?", "get"));
+    }
+
+    #[test]
+    fn select_by_pattern() {
+        let classes = vec!["a.UserTest".to_owned(), "a.OrderTest".to_owned()];
+        let filter = TestFilter {
+            pattern: Some("User*#create*".into()),
+            ..Default::default()
+        };
+        let selectors = filter.select(Path::new("/nonexistent"), &classes).unwrap();
+        assert_eq!(
+            selectors,
+            vec![Selector {
+                class: "a.UserTest".into(),
+                method: Some("create*".into())
+            }]
+        );
+        assert_eq!(
+            filter.runner_args(&selectors),
+            vec!["a.UserTest#create*".to_owned()]
+        );
+    }
+
+    #[test]
+    fn class_errors_run_the_whole_class_again() {
+        let target = std::env::temp_dir().join(format!("jargo-failed-{}", std::process::id()));
+        let classes = vec!["a.UserTest".to_owned(), "a.OrderTest".to_owned()];
+        let failed = TestFilter {
+            failed: true,
+            ..Default::default()
+        };
+        let result = |class: &str, name: &str, status| TestResult {
+            name: name.into(),
+            status,
+            ..TestResult::class_error(class, "")
+        };
+        store_failed(
+            &target,
+            &[
+                TestResult::class_error("a.UserTest", "timed out after 10s"),
+                result("a.UserTest", "create", Status::Failed),
+                result("a.OrderTest", "cancel[1]", Status::Failed),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            failed.select(&target, &classes).unwrap(),
+            vec![
+                Selector {
+                    class: "a.OrderTest".into(),
+                    method: Some("cancel".into())
+                },
+                Selector {
+                    class: "a.UserTest".into(),
+                    method: None
+                },
+            ]
+        );
+
+        // the class ran again without errors
+        store_failed(&target, &[result("a.UserTest", "create", Status::Passed)]).unwrap();
+        assert_eq!(failed.select(&target, &classes).unwrap().len(), 1);
+        std::fs::remove_dir_all(target).unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Error};
+
+use crate::compile::write_argfile;
+use crate::deploader;
+use crate::project::{Artifact, Project};
+use crate::test::filter::Selector;
+use crate::test::{
+    file_name, path_string, print_result, Framework, Status, TestFilter, TestResult,
+};
+
+/// lines on stdout that start with this are events from the runner, anything else is test output
+const EVENT_PREFIX: &str = "##jargo|";
+
+const EVENTS: (&str, &str) = ("Events.java", include_str!("runner/Events.java"));
+const SELECTION: (&str, &str) = ("Selection.java", include_str!("runner/Selection.java"));
+const JUNIT4: (&str, &str) = ("JargoJUnit4.java", include_str!("runner/JargoJUnit4.java"));
+const JUNIT_PLATFORM: (&str, &str) = (
+    "JargoJUnitPlatform.java",
+    include_str!("runner/JargoJUnitPlatform.java"),
+);
+const TESTNG: (&str, &str) = ("JargoTestNG.java", include_str!("runner/JargoTestNG.java"));
+
+/// used when the version can't be derived from the junit-platform jars of the project
+const DEFAULT_PLATFORM_VERSION: &str = "1.10.2";
+
+impl Framework {
+    fn runner(&self) -> (&'static str, (&'static str, &'static str)) {
+        match self {
+            Framework::JUnit4 => ("jargo.runner.JargoJUnit4", JUNIT4),
+            Framework::JUnitPlatform => ("jargo.runner.JargoJUnitPlatform", JUNIT_PLATFORM),
+            Framework::TestNG => ("jargo.runner.JargoTestNG", TESTNG),
+        }
+    }
+
+    fn dir_name(&self) -> &'static str {
+        match self {
+            Framework::JUnit4 => "junit4",
+            Framework::JUnitPlatform => "junit-platform",
+            Framework::TestNG => "testng",
+        }
+    }
+}
+
+/// What the runner in the test JVM reports
+#[derive(Debug)]
+enum Event {
+    ClassStart(String),
+    TestEnd(TestResult),
+}
+
+/// How a test JVM ended
+enum Exit {
+    Finished,
+    /// the class that was running took longer than the timeout
+    TimedOut,
+    /// the JVM stopped with an error while running a class
+    Crashed(ExitStatus),
+}
+
+/// What happened in one test JVM
+struct Outcome {
+    exit: Exit,
+    /// the classes that have started, in order
+    started: Vec<String>,
+}
+
+/// Makes the runner and its dependencies available, returns the classpath for the test JVMs
+pub(super) fn prepare(
+    project: &Project,
+    framework: Framework,
+    mut classpath: Vec<String>,
+) -> Result<Vec<String>, Error> {
+    if framework == Framework::JUnitPlatform {
+        add_console_launcher(project, &mut classpath)?;
+    }
+    let work_dir = PathBuf::from(&project.target_dir).join("jargo");
+    let runner_classes = compile_runner(project, &work_dir, framework, &classpath)?;
+    classpath.insert(0, path_string(&runner_classes));
+    Ok(classpath)
+}
+
+/// One of the JVMs that run the tests, with its own working directory and temp dir
+pub(super) struct Fork<'a> {
+    pub(super) project: &'a Project,
+    pub(super) framework: Framework,
+    pub(super) classpath: &'a [String],
+    pub(super) filter: &'a TestFilter,
+    /// starts at 1
+    pub(super) number: usize,
+    /// when there are more forks, the output of the tests is prefixed with the fork number
+    pub(super) prefix_output: bool,
+}
+
+impl Fork<'_> {
+    /// Runs the test classes and collects the results.
+    /// When a class times out or crashes the JVM, it is reported as an error
+    /// and the classes that haven't started yet are run in a new JVM.
+    pub(super) fn run(&self, mut selectors: Vec<Selector>) -> Result<Vec<TestResult>, Error> {
+        let dir = PathBuf::from(&self.project.target_dir)
+            .join("jargo/forks")
+            .join(self.number.to_string());
+        fs::create_dir_all(dir.join("tmp"))?;
+
+        let mut results = vec![];
+        while !selectors.is_empty() {
+            let outcome = self.launch(&dir, &selectors, &mut results)?;
+            let timeout = self.project.test.timeout.unwrap_or_default();
+            let (message, duration) = match outcome.exit {
+                Exit::Finished => break,
+                Exit::TimedOut => (format!("timed out after {}s", timeout.as_secs()), timeout),
+                Exit::Crashed(status) => (
+                    format!("the test JVM exited with {}", status),
+                    Duration::ZERO,
+                ),
+            };
+            let class = outcome
+                .started
+                .last()
+                .ok_or_else(|| anyhow!("{} before running any test class", message))?;
+            // the timeout counts as the duration, so that the class is scheduled as a slow one
+            let result = TestResult {
+                duration,
+                ..TestResult::class_error(class, &message)
+            };
+            print_result(&result);
+            results.push(result);
+            selectors.retain(|s| !outcome.started.contains(&s.class));
+        }
+        Ok(results)
+    }
+
+    fn launch(
+        &self,
+        dir: &Path,
+        selectors: &[Selector],
+        results: &mut Vec<TestResult>,
+    ) -> Result<Outcome, Error> {
+        let (main_class, _) = self.framework.runner();
+        let mut args = vec![
+            format!("-Djava.io.tmpdir={}", path_string(&dir.join("tmp"))),
+            format!("-Dbasedir={}", self.project.project_root),
+            "-cp".to_owned(),
+            std::env::join_paths(self.classpath)?
+                .to_string_lossy()
+                .to_string(),
+            main_class.to_owned(),
+        ];
+        args.extend(self.filter.runner_args(selectors));
+        let argfile = dir.join("test.args");
+        write_argfile(&argfile, &args)?;
+
+        let mut child = self
+            .project
+            .java()
+            .arg(format!("@{}", argfile.to_string_lossy()))
+            .current_dir(dir)
+            .env("JARGO_FORK", self.number.to_string())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("failed to execute java: {}", e))?;
+
+        // the output is read in another thread, so that a hanging test can be noticed
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("no stdout"))?;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut started = vec![];
+        let mut class_start = Instant::now();
+        loop {
+            let line = match self.project.test.timeout {
+                Some(timeout) => {
+                    match lines.recv_timeout(timeout.saturating_sub(class_start.elapsed())) {
+                        Ok(line) => line,
+                        Err(RecvTimeoutError::Timeout) => {
+                            let _ = child.kill();
+                            child.wait()?;
+                            return Ok(Outcome {
+                                exit: Exit::TimedOut,
+                                started,
+                            });
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match lines.recv() {
+                    Ok(line) => line,
+                    Err(_) => break,
+                },
+            }?;
+            let (output, event) = split_event(&line);
+            if event.is_none() || !output.is_empty() {
+                if self.prefix_output {
+                    println!("[fork {}] {}", self.number, output);
+                } else {
+                    println!("{}", output);
+                }
+            }
+            match event {
+                Some(Event::TestEnd(result)) => {
+                    print_result(&result);
+                    results.push(result);
+                }
+                Some(Event::ClassStart(class)) => {
+                    started.push(class);
+                    class_start = Instant::now();
+                }
+                None => {}
+            }
+        }
+        let status = child.wait()?;
+        let exit = if status.success() {
+            Exit::Finished
+        } else {
+            Exit::Crashed(status)
+        };
+        Ok(Outcome { exit, started })
+    }
+}
+
+/// JUnit 5 tests are run by the junit platform launcher, that comes with the console launcher jar
+fn add_console_launcher(project: &Project, classpath: &mut Vec<String>) -> Result<(), Error> {
+    let names: Vec<String> = classpath.iter().map(|jar| file_name(jar)).collect();
+    if names
+        .iter()
+        .any(|n| n.starts_with("junit-platform-console-standalone-"))
+    {
+        return Ok(());
+    }
+    let version = names
+        .iter()
+        .find_map(|n| {
+            n.strip_prefix("junit-platform-commons-")
+                .and_then(|v| v.strip_suffix(".jar"))
+        })
+        .unwrap_or(DEFAULT_PLATFORM_VERSION);
+    let console_launcher = Artifact::new(
+        "org.junit.platform",
+        "junit-platform-console-standalone",
+        version,
+    );
+    classpath.extend(deploader::load_tool(project, console_launcher)?);
+    Ok(())
+}
+
+/// The runner is shipped as java source and compiled against the test classpath of the project,
+/// so that it works with the version of the framework the project uses.
+/// It is only compiled again when the source or the JDK has changed.
+fn compile_runner(
+    project: &Project,
+    work_dir: &Path,
+    framework: Framework,
+    classpath: &[String],
+) -> Result<PathBuf, Error> {
+    let runner_dir = work_dir.join("runner").join(framework.dir_name());
+    let src_dir = runner_dir.join("src/jargo/runner");
+    let classes = runner_dir.join("classes");
+    fs::create_dir_all(&src_dir)?;
+
+    let (_, runner) = framework.runner();
+    let mut changed = !classes.exists();
+    // the classes of a newer JDK can't be run by an older one
+    let javac = project.javac().get_program().to_string_lossy().to_string();
+    let javac_file = runner_dir.join("javac");
+    if fs::read_to_string(&javac_file).ok() != Some(javac.clone()) {
+        fs::write(&javac_file, &javac)?;
+        changed = true;
+    }
+    let mut sources = vec![];
+    for (name, source) in [EVENTS, SELECTION, runner] {
+        let path = src_dir.join(name);
+        if fs::read_to_string(&path).ok().as_deref() != Some(source) {
+            fs::write(&path, source)?;
+            changed = true;
+        }
+        sources.push(path);
+    }
+    if changed {
+        let output = project
+            .javac()
+            .arg("-d")
+            .arg(&classes)
+            .arg("-nowarn")
+            .arg("-classpath")
+            .arg(std::env::join_paths(classpath)?)
+            .args(&sources)
+            .output()
+            .map_err(|e| anyhow!("failed to execute javac: {}", e))?;
+        if !output.status.success() {
+            let _ = fs::remove_dir_all(&classes);
+            return Err(anyhow!(
+                "could not compile the test runner: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    }
+    Ok(classes)
+}
+
+/// The test output in a line, and the event after it. The runner starts every event on a new
+/// line, but output that bypasses System.out can still end up in front of one.
+fn split_event(line: &str) -> (&str, Option<Event>) {
+    match line.find(EVENT_PREFIX) {
+        Some(start) => match parse_event(&line[start..]) {
+            Some(event) => (&line[..start], Some(event)),
+            None => (line, None),
+        },
+        None => (line, None),
+    }
+}
+
+fn parse_event(line: &str) -> Option<Event> {
+    let fields: Vec<String> = line
+        .strip_prefix(EVENT_PREFIX)?
+        .split('|')
+        .map(unescape)
+        .collect();
+    match fields.first()?.as_str() {
+        "class-start" => Some(Event::ClassStart(fields.get(1)?.clone())),
+        "test-end" if fields.len() >= 9 => {
+            let status = match fields[3].as_str() {
+                "passed" => Status::Passed,
+                "failed" => Status::Failed,
+                "error" => Status::Error,
+                _ => Status::Skipped,
+            };
+            let non_empty = |s: &String| if s.is_empty() { None } else { Some(s.clone()) };
+            Some(Event::TestEnd(TestResult {
+                class: fields[1].clone(),
+                name: fields[2].clone(),
+                status,
+                duration: Duration::from_millis(fields[4].parse().unwrap_or_default()),
+                message: non_empty(&fields[5]),
+                trace: non_empty(&fields[6]),
+                stdout: non_empty(&fields[7]),
+                stderr: non_empty(&fields[8]),
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// reverses the escaping in Events.java
+fn unescape(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('p') => result.push('|'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::launcher::{split_event, Event};
+    use crate::test::Status;
+
+    #[test]
+    fn events_after_output() {
+        let (output, event) = split_event("##jargo|class-start|a.ATest");
+        assert_eq!(output, "");
+        assert!(matches!(event, Some(Event::ClassStart(class)) if class == "a.ATest"));
+
+        // System.out.print("x") right before the test ended
+        let (output, event) = split_event("x##jargo|test-end|a.ATest|prints|passed|5|||x|");
+        assert_eq!(output, "x");
+        let Some(Event::TestEnd(result)) = event else {
+            panic!("no test-end in {:?}", event);
+        };
+        assert_eq!(result.name, "prints");
+        assert_eq!(result.status, Status::Passed);
+        assert_eq!(result.stdout.as_deref(), Some("x"));
+
+        let (output, event) = split_event("just output, with ##jargo in it");
+        assert_eq!(output, "just output, with ##jargo in it");
+        assert!(event.is_none());
+    }
+}
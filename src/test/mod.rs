@@ -0,0 +1,334 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Error};
+use colored::Colorize;
+
+use crate::classfile::ClassFile;
+use crate::compile::{TARGET_MAIN, TARGET_TEST};
+use crate::deploader::Classpath;
+use crate::package::dir_entries;
+use crate::project::Project;
+
+mod filter;
+mod launcher;
+mod report;
+mod schedule;
+
+pub use filter::TestFilter;
+pub use report::{write_json_summary, TEST_REPORTS};
+
+/// the annotations that mark a class as a test class, for JUnit 4, JUnit 5 and TestNG
+const TEST_ANNOTATIONS: [&str; 8] = [
+    "Lorg/junit/Test;",
+    "Lorg/junit/runner/RunWith;",
+    "Lorg/junit/jupiter/api/Test;",
+    "Lorg/junit/jupiter/params/ParameterizedTest;",
+    "Lorg/junit/jupiter/api/RepeatedTest;",
+    "Lorg/junit/jupiter/api/TestFactory;",
+    "Lorg/junit/jupiter/api/TestTemplate;",
+    "Lorg/testng/annotations/Test;",
+];
+
+/// The test frameworks that jargo can run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framework {
+    JUnit4,
+    /// JUnit 5, and JUnit 4 through the vintage engine
+    JUnitPlatform,
+    TestNG,
+}
+
+impl Framework {
+    /// determines the framework from the names of the jars on the test classpath
+    fn detect(jars: &[String]) -> Option<Self> {
+        let names: Vec<String> = jars.iter().map(|jar| file_name(jar)).collect();
+        let has = |prefix: &str| names.iter().any(|n| n.starts_with(prefix));
+        if has("junit-jupiter-api-") || has("junit-platform-") {
+            Some(Framework::JUnitPlatform)
+        } else if has("testng-") {
+            Some(Framework::TestNG)
+        } else if names.iter().any(|n| {
+            n.strip_prefix("junit-")
+                .is_some_and(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+        }) {
+            Some(Framework::JUnit4)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Passed,
+    /// an assertion failed
+    Failed,
+    /// the test threw an unexpected exception
+    Error,
+    Skipped,
+}
+
+/// the outcome of one test method
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub class: String,
+    pub name: String,
+    pub status: Status,
+    pub duration: Duration,
+    pub message: Option<String>,
+    pub trace: Option<String>,
+    /// what the test wrote to System.out
+    pub stdout: Option<String>,
+    /// what the test wrote to System.err
+    pub stderr: Option<String>,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::Failed => "failed",
+            Status::Error => "error",
+            Status::Skipped => "skipped",
+        }
+    }
+}
+
+/// the name of the result of a failure outside of the test methods
+const CLASS_ERROR: &str = "(class)";
+
+impl TestResult {
+    pub fn id(&self) -> String {
+        format!("{}::{}", self.class, self.name)
+    }
+
+    /// for a failure outside of the test methods, like a static initializer or a timeout
+    pub fn class_error(class: &str, message: &str) -> Self {
+        Self {
+            class: class.into(),
+            name: CLASS_ERROR.into(),
+            status: Status::Error,
+            duration: Duration::ZERO,
+            message: Some(message.into()),
+            trace: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    /// failed or error
+    pub fn is_failure(&self) -> bool {
+        self.status == Status::Failed || self.status == Status::Error
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TestSummary {
+    pub results: Vec<TestResult>,
+    pub duration: Duration,
+}
+
+impl TestSummary {
+    pub fn count(&self, status: Status) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+
+    /// failures and errors
+    pub fn failed(&self) -> Vec<&TestResult> {
+        self.results.iter().filter(|r| r.is_failure()).collect()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed().is_empty()
+    }
+}
+
+/// runs the test stage, after the tests are compiled
+///
+/// 1. finds the test classes by their annotations and selects the ones that match the filter
+/// 2. runs them with a runner for the framework that is on the test classpath, in one or more JVMs
+///    that are balanced by how long the classes took before
+/// 3. prints the results, writes the reports to target/test-reports and keeps the failed tests
+///    for the next run
+pub fn run(
+    project: &Project,
+    classpath: &Classpath,
+    filter: &TestFilter,
+) -> Result<TestSummary, Error> {
+    let target = PathBuf::from(&project.target_dir);
+    let test_classes = discover(&target.join(TARGET_TEST))?;
+    let selectors = filter.select(&target, &test_classes)?;
+    if selectors.is_empty() {
+        println!("{}", "No tests found".yellow());
+        return Ok(TestSummary::default());
+    }
+
+    let jars = classpath.test_runtime();
+    let framework = Framework::detect(&jars)
+        .ok_or_else(|| anyhow!("no test framework (JUnit or TestNG) found in test-dependencies"))?;
+
+    let mut test_classpath = vec![
+        path_string(&target.join(TARGET_TEST)),
+        path_string(&target.join(TARGET_MAIN)),
+    ];
+    test_classpath.extend(jars);
+
+    let test_classpath = launcher::prepare(project, framework, test_classpath)?;
+    let forks = schedule::balance(
+        &selectors,
+        &schedule::read_durations(&target)?,
+        project.test.forks.count(),
+    );
+
+    let classes: BTreeSet<&String> = selectors.iter().map(|s| &s.class).collect();
+    if forks.len() > 1 {
+        println!(
+            "{} {} test classes in {} JVMs",
+            "Testing".green(),
+            classes.len(),
+            forks.len()
+        );
+    } else {
+        println!("{} {} test classes", "Testing".green(), classes.len());
+    }
+    let start = Instant::now();
+    let prefix_output = forks.len() > 1;
+    let results: Vec<TestResult> = thread::scope(|scope| {
+        let handles: Vec<_> = forks
+            .into_iter()
+            .enumerate()
+            .map(|(i, selectors)| {
+                let fork = launcher::Fork {
+                    project,
+                    framework,
+                    classpath: &test_classpath,
+                    filter,
+                    number: i + 1,
+                    prefix_output,
+                };
+                scope.spawn(move || fork.run(selectors))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow!("a test fork panicked")))
+            })
+            .collect::<Result<Vec<_>, Error>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect();
+    schedule::store_durations(&target, &results)?;
+    filter::store_failed(&target, &results)?;
+    report::write_xml(&target, &results)?;
+    let summary = TestSummary {
+        results,
+        duration: start.elapsed(),
+    };
+    print_summary(&summary);
+    Ok(summary)
+}
+
+/// finds the concrete classes that contain test annotations
+fn discover(test_classes: &Path) -> Result<Vec<String>, Error> {
+    let mut classes = vec![];
+    for (name, file) in dir_entries(test_classes)? {
+        if let Some(class_name) = name.strip_suffix(".class") {
+            if class_name.contains('$') {
+                continue;
+            }
+            let class = ClassFile::parse(&fs::read(&file)?)?;
+            if !class.is_abstract() && TEST_ANNOTATIONS.iter().any(|a| class.has_utf8(a)) {
+                classes.push(class_name.replace('/', "."));
+            }
+        }
+    }
+    Ok(classes)
+}
+
+/// prints one line for a finished test
+fn print_result(result: &TestResult) {
+    let status = match result.status {
+        Status::Passed => "ok".green(),
+        Status::Failed => "FAILED".red(),
+        Status::Error => "ERROR".red(),
+        Status::Skipped => "skipped".yellow(),
+    };
+    println!("test {} ... {}", result.id(), status);
+}
+
+fn print_summary(summary: &TestSummary) {
+    let failed = summary.failed();
+    if !failed.is_empty() {
+        println!("\nfailures:\n");
+        for result in &failed {
+            println!("---- {} ----", result.id());
+            if let Some(trace) = &result.trace {
+                println!("{}", trace.trim_end());
+            } else if let Some(message) = &result.message {
+                println!("{}", message);
+            }
+            println!();
+        }
+        println!("failures:");
+        for result in &failed {
+            println!("    {}", result.id());
+        }
+    }
+    let outcome = if summary.is_success() {
+        "ok".green()
+    } else {
+        "FAILED".red()
+    };
+    println!(
+        "\ntest result: {}. {} passed; {} failed; {} skipped; finished in {:.2}s\n",
+        outcome,
+        summary.count(Status::Passed),
+        failed.len(),
+        summary.count(Status::Skipped),
+        summary.duration.as_secs_f64()
+    );
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::Framework;
+
+    #[test]
+    fn detect_framework() {
+        let detect = |jars: &[&str]| {
+            Framework::detect(&jars.iter().map(|j| j.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            detect(&["/repo/junit/junit/4.8.2/junit-4.8.2.jar"]),
+            Some(Framework::JUnit4)
+        );
+        assert_eq!(
+            detect(&["junit-4.13.jar", "junit-jupiter-api-5.10.2.jar"]),
+            Some(Framework::JUnitPlatform)
+        );
+        assert_eq!(detect(&["testng-7.9.0.jar"]), Some(Framework::TestNG));
+        assert_eq!(
+            detect(&["hamcrest-core-1.1.jar", "mockito-core-1.9.5.jar"]),
+            None
+        );
+    }
+}
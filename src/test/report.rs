@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Error;
+use serde_json::json;
+
+use crate::test::{Status, TestResult, TestSummary};
+
+/// where the reports are written, relative to the target directory
+pub const TEST_REPORTS: &str = "test-reports";
+
+/// Writes a Surefire compatible TEST-<class>.xml for every test class that ran,
+/// the format that Jenkins, GitLab and most other CI servers read.
+pub(super) fn write_xml(target: &Path, results: &[TestResult]) -> Result<(), Error> {
+    let dir = target.join(TEST_REPORTS);
+    fs::create_dir_all(&dir)?;
+
+    let mut classes: BTreeMap<&str, Vec<&TestResult>> = BTreeMap::new();
+    for result in results {
+        classes.entry(&result.class).or_default().push(result);
+    }
+    for (class, results) in classes {
+        fs::write(
+            dir.join(format!("TEST-{}.xml", class)),
+            testsuite(class, &results),
+        )?;
+    }
+    Ok(())
+}
+
+/// writes test-reports/summary.json in the target directory, with the counts and the outcome of every test
+pub fn write_json_summary(target: &Path, summary: &TestSummary) -> Result<PathBuf, Error> {
+    let results: Vec<_> = summary
+        .results
+        .iter()
+        .map(|r| {
+            json!({
+                "class": r.class,
+                "name": r.name,
+                "status": r.status.as_str(),
+                "duration": r.duration.as_secs_f64(),
+                "message": r.message,
+            })
+        })
+        .collect();
+    let json = json!({
+        "success": summary.is_success(),
+        "tests": summary.results.len(),
+        "passed": summary.count(Status::Passed),
+        "failed": summary.count(Status::Failed),
+        "errors": summary.count(Status::Error),
+        "skipped": summary.count(Status::Skipped),
+        "duration": summary.duration.as_secs_f64(),
+        "results": results,
+    });
+
+    let dir = target.join(TEST_REPORTS);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join("summary.json");
+    fs::write(&path, serde_json::to_string_pretty(&json)?)?;
+    Ok(path)
+}
+
+fn testsuite(class: &str, results: &[&TestResult]) -> String {
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let duration: Duration = results.iter().map(|r| r.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{}\">\n",
+        escape(class),
+        results.len(),
+        count(Status::Failed),
+        count(Status::Error),
+        count(Status::Skipped),
+        seconds(duration)
+    ));
+    for result in results {
+        xml.push_str(&format!(
+            "  <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+            escape(&result.name),
+            escape(class),
+            seconds(result.duration)
+        ));
+        if result.status == Status::Passed && result.stdout.is_none() && result.stderr.is_none() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n");
+        match result.status {
+            Status::Failed | Status::Error => {
+                let element = if result.status == Status::Failed {
+                    "failure"
+                } else {
+                    "error"
+                };
+                xml.push_str(&format!(
+                    "    <{} message=\"{}\" type=\"{}\">{}</{}>\n",
+                    element,
+                    escape(result.message.as_deref().unwrap_or_default()),
+                    escape(&exception_type(result)),
+                    escape(result.trace.as_deref().unwrap_or_default()),
+                    element
+                ));
+            }
+            Status::Skipped => match &result.message {
+                Some(message) => {
+                    xml.push_str(&format!("    <skipped message=\"{}\"/>\n", escape(message)))
+                }
+                None => xml.push_str("    <skipped/>\n"),
+            },
+            Status::Passed => {}
+        }
+        if let Some(stdout) = &result.stdout {
+            xml.push_str(&format!(
+                "    <system-out>{}</system-out>\n",
+                escape(stdout)
+            ));
+        }
+        if let Some(stderr) = &result.stderr {
+            xml.push_str(&format!(
+                "    <system-err>{}</system-err>\n",
+                escape(stderr)
+            ));
+        }
+        xml.push_str("  </testcase>\n");
+    }
+    xml.push_str("</testsuite>\n");
+    xml
+}
+
+/// the class of the exception, from the first line of the stack trace
+fn exception_type(result: &TestResult) -> String {
+    let first_line = result
+        .trace
+        .as_deref()
+        .and_then(|t| t.lines().next())
+        .unwrap_or_default();
+    first_line
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_owned()
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64())
+}
+
+/// escapes text for use in attributes and elements,
+/// and leaves out the control characters that XML 1.0 doesn't allow
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::test::report::testsuite;
+    use crate::test::{Status, TestResult};
+
+    fn result(name: &str, status: Status) -> TestResult {
+        TestResult {
+            class: "a.UserTest".into(),
+            name: name.into(),
+            status,
+            duration: Duration::from_millis(1500),
+            message: None,
+            trace: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    #[test]
+    fn surefire_xml() {
+        let passed = result("create", Status::Passed);
+        let mut failed = result("delete", Status::Failed);
+        failed.message = Some("expected <1> but was <2>".into());
+        failed.trace = Some("java.lang.AssertionError: expected <1> but was <2>\n\tat a.UserTest.delete(UserTest.java:12)\n".into());
+        failed.stdout = Some("deleting \u{1b}[1m\n".into());
+
+        let xml = testsuite("a.UserTest", &[&passed, &failed]);
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="a.UserTest" tests="2" failures="1" errors="0" skipped="0" time="3.000">
+  <testcase name="create" classname="a.UserTest" time="1.500"/>
+  <testcase name="delete" classname="a.UserTest" time="1.500">
+    <failure message="expected &lt;1&gt; but was &lt;2&gt;" type="java.lang.AssertionError">java.lang.AssertionError: expected &lt;1&gt; but was &lt;2&gt;
+	at a.UserTest.delete(UserTest.java:12)
+</failure>
+    <system-out>deleting [1m
+</system-out>
+  </testcase>
+</testsuite>
+"#
+        );
+    }
+}
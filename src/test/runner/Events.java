@@ -0,0 +1,124 @@
+package jargo.runner;
+
+import java.io.ByteArrayOutputStream;
+import java.io.IOException;
+import java.io.OutputStream;
+import java.io.PrintStream;
+
+/**
+ * Writes the test events that jargo reads, one per line on the original stdout.
+ * Fields are separated by '|', so '|', '\' and line breaks in the fields are escaped.
+ */
+public final class Events {
+    private static final PrintStream OUT = System.out;
+    private static final ByteArrayOutputStream STDOUT = new ByteArrayOutputStream();
+    private static final ByteArrayOutputStream STDERR = new ByteArrayOutputStream();
+    /** whether the tests ended their output on stdout with a line break, guarded by OUT */
+    private static boolean atLineStart = true;
+
+    private Events() {
+    }
+
+    /**
+     * Output of the tests still goes to the console, but is also kept until the next test ends,
+     * so that it can be reported with that test.
+     */
+    public static void captureOutput() {
+        System.setOut(new PrintStream(new Tee(OUT, STDOUT, true), true));
+        System.setErr(new PrintStream(new Tee(System.err, STDERR, false), true));
+    }
+
+    public static void classStart(String className) {
+        emit("class-start", className);
+    }
+
+    public static void testEnd(String className, String name, String status, long millis, Throwable failure) {
+        String message = failure == null || failure.getMessage() == null ? "" : failure.getMessage();
+        String trace = failure == null ? "" : trace(failure);
+        emit("test-end", className, name, status, Long.toString(millis), message, trace,
+                take(STDOUT), take(STDERR));
+    }
+
+    /** AssertionErrors are test failures, any other exception is an error in the test */
+    public static String failureStatus(Throwable failure) {
+        return failure instanceof AssertionError ? "failed" : "error";
+    }
+
+    private static String take(ByteArrayOutputStream captured) {
+        synchronized (captured) {
+            String output = captured.toString();
+            captured.reset();
+            return output;
+        }
+    }
+
+    private static String trace(Throwable failure) {
+        java.io.StringWriter writer = new java.io.StringWriter();
+        failure.printStackTrace(new java.io.PrintWriter(writer));
+        return writer.toString();
+    }
+
+    private static synchronized void emit(String... fields) {
+        StringBuilder line = new StringBuilder("##jargo");
+        for (String field : fields) {
+            line.append('|').append(escape(field));
+        }
+        synchronized (OUT) {
+            // an event after output like System.out.print("x") starts on a line of its own
+            if (!atLineStart) {
+                OUT.println();
+            }
+            OUT.println(line);
+            OUT.flush();
+            atLineStart = true;
+        }
+    }
+
+    private static String escape(String field) {
+        return field.replace("\\", "\\\\").replace("|", "\\p").replace("\r", "\\r").replace("\n", "\\n");
+    }
+
+    /** writes to the console and to the capture buffer */
+    private static final class Tee extends OutputStream {
+        private final OutputStream console;
+        private final ByteArrayOutputStream captured;
+        private final boolean stdout;
+
+        Tee(OutputStream console, ByteArrayOutputStream captured, boolean stdout) {
+            this.console = console;
+            this.captured = captured;
+            this.stdout = stdout;
+        }
+
+        @Override
+        public void write(int b) throws IOException {
+            synchronized (OUT) {
+                console.write(b);
+                if (stdout) {
+                    atLineStart = b == '\n';
+                }
+            }
+            synchronized (captured) {
+                captured.write(b);
+            }
+        }
+
+        @Override
+        public void write(byte[] b, int off, int len) throws IOException {
+            synchronized (OUT) {
+                console.write(b, off, len);
+                if (stdout && len > 0) {
+                    atLineStart = b[off + len - 1] == '\n';
+                }
+            }
+            synchronized (captured) {
+                captured.write(b, off, len);
+            }
+        }
+
+        @Override
+        public void flush() throws IOException {
+            console.flush();
+        }
+    }
+}
@@ -0,0 +1,120 @@
+package jargo.runner;
+
+import java.util.ArrayList;
+import java.util.List;
+
+import org.junit.runner.Description;
+import org.junit.runner.JUnitCore;
+import org.junit.runner.Request;
+import org.junit.runner.Runner;
+import org.junit.runner.manipulation.Filter;
+import org.junit.runner.manipulation.Filterable;
+import org.junit.runner.manipulation.NoTestsRemainException;
+import org.junit.runner.notification.Failure;
+import org.junit.runner.notification.RunListener;
+
+/**
+ * Runs JUnit 4 test classes with JUnitCore and reports every test to jargo.
+ */
+public class JargoJUnit4 extends RunListener {
+    private long start;
+    private Failure failure;
+    private boolean skipped;
+
+    public static void main(String[] args) throws Exception {
+        Selection selection = Selection.parse(args);
+        Events.captureOutput();
+        JUnitCore core = new JUnitCore();
+        core.addListener(new JargoJUnit4());
+        for (String className : selection.classNames()) {
+            Class<?> testClass = Class.forName(className, false, JargoJUnit4.class.getClassLoader());
+            Runner runner = Request.aClass(testClass).getRunner();
+            if ((selection.hasTags() || selection.hasMethodPatterns()) && runner instanceof Filterable) {
+                try {
+                    ((Filterable) runner).filter(new SelectionFilter(selection));
+                } catch (NoTestsRemainException e) {
+                    continue;
+                }
+            }
+            Events.classStart(className);
+            core.run(runner);
+        }
+        System.exit(0);
+    }
+
+    @Override
+    public void testStarted(Description description) {
+        start = System.currentTimeMillis();
+        failure = null;
+        skipped = false;
+    }
+
+    @Override
+    public void testFailure(Failure failure) {
+        Description description = failure.getDescription();
+        if (description.getMethodName() == null) {
+            // @BeforeClass or @AfterClass failed, or the class could not be instantiated
+            Events.testEnd(description.getClassName(), "(class)", "error", 0, failure.getException());
+        } else {
+            this.failure = failure;
+        }
+    }
+
+    @Override
+    public void testAssumptionFailure(Failure failure) {
+        skipped = true;
+    }
+
+    @Override
+    public void testIgnored(Description description) {
+        Events.testEnd(description.getClassName(), name(description), "skipped", 0, null);
+    }
+
+    @Override
+    public void testFinished(Description description) {
+        long millis = System.currentTimeMillis() - start;
+        if (failure != null) {
+            Throwable exception = failure.getException();
+            Events.testEnd(description.getClassName(), name(description), Events.failureStatus(exception), millis, exception);
+        } else {
+            Events.testEnd(description.getClassName(), name(description), skipped ? "skipped" : "passed", millis, null);
+        }
+    }
+
+    private static String name(Description description) {
+        return description.getMethodName() == null ? "(class)" : description.getMethodName();
+    }
+
+    /** selects methods by name and by the categories on the method and its class */
+    private static class SelectionFilter extends Filter {
+        private final Selection selection;
+
+        SelectionFilter(Selection selection) {
+            this.selection = selection;
+        }
+
+        @Override
+        public boolean shouldRun(Description description) {
+            if (description.isTest()) {
+                List<String> categories = new ArrayList<>(Selection.categories(
+                        description.getAnnotations().toArray(new java.lang.annotation.Annotation[0])));
+                if (description.getTestClass() != null) {
+                    categories.addAll(Selection.categories(description.getTestClass().getAnnotations()));
+                }
+                return selection.matches(description.getClassName(), description.getMethodName())
+                        && selection.matchesTags(categories);
+            }
+            for (Description child : description.getChildren()) {
+                if (shouldRun(child)) {
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        @Override
+        public String describe() {
+            return "jargo selection";
+        }
+    }
+}
@@ -0,0 +1,112 @@
+package jargo.runner;
+
+import static org.junit.platform.engine.discovery.DiscoverySelectors.selectClass;
+
+import java.util.ArrayList;
+import java.util.List;
+import java.util.Map;
+import java.util.concurrent.ConcurrentHashMap;
+
+import org.junit.platform.engine.DiscoverySelector;
+import org.junit.platform.engine.FilterResult;
+import org.junit.platform.engine.TestExecutionResult;
+import org.junit.platform.engine.TestSource;
+import org.junit.platform.engine.support.descriptor.ClassSource;
+import org.junit.platform.engine.support.descriptor.MethodSource;
+import org.junit.platform.launcher.Launcher;
+import org.junit.platform.launcher.LauncherDiscoveryRequest;
+import org.junit.platform.launcher.PostDiscoveryFilter;
+import org.junit.platform.launcher.TagFilter;
+import org.junit.platform.launcher.TestExecutionListener;
+import org.junit.platform.launcher.TestIdentifier;
+import org.junit.platform.launcher.core.LauncherDiscoveryRequestBuilder;
+import org.junit.platform.launcher.core.LauncherFactory;
+
+/**
+ * Runs test classes on the JUnit platform (JUnit 5 and the vintage engine for JUnit 4)
+ * from the console launcher jar and reports every test to jargo.
+ */
+public class JargoJUnitPlatform implements TestExecutionListener {
+    private final Map<String, Long> starts = new ConcurrentHashMap<>();
+
+    public static void main(String[] args) {
+        Selection selection = Selection.parse(args);
+        Events.captureOutput();
+        List<DiscoverySelector> selectors = new ArrayList<>();
+        for (String className : selection.classNames()) {
+            selectors.add(selectClass(className));
+        }
+        LauncherDiscoveryRequestBuilder builder = LauncherDiscoveryRequestBuilder.request().selectors(selectors);
+        if (!selection.includeTags.isEmpty()) {
+            builder.filters(TagFilter.includeTags(selection.includeTags));
+        }
+        if (!selection.excludeTags.isEmpty()) {
+            builder.filters(TagFilter.excludeTags(selection.excludeTags));
+        }
+        if (selection.hasMethodPatterns()) {
+            builder.filters((PostDiscoveryFilter) descriptor -> {
+                TestSource source = descriptor.getSource().orElse(null);
+                if (source instanceof MethodSource) {
+                    MethodSource method = (MethodSource) source;
+                    return FilterResult.includedIf(selection.matches(method.getClassName(), method.getMethodName()));
+                }
+                return FilterResult.included("not a method");
+            });
+        }
+        LauncherDiscoveryRequest request = builder.build();
+        Launcher launcher = LauncherFactory.create();
+        launcher.execute(request, new JargoJUnitPlatform());
+        System.exit(0);
+    }
+
+    @Override
+    public void executionStarted(TestIdentifier id) {
+        TestSource source = id.getSource().orElse(null);
+        if (source instanceof ClassSource) {
+            Events.classStart(((ClassSource) source).getClassName());
+        }
+        starts.put(id.getUniqueId(), System.currentTimeMillis());
+    }
+
+    @Override
+    public void executionSkipped(TestIdentifier id, String reason) {
+        TestSource source = id.getSource().orElse(null);
+        if (source instanceof MethodSource) {
+            Events.testEnd(((MethodSource) source).getClassName(), name(id, (MethodSource) source), "skipped", 0, null);
+        } else if (source instanceof ClassSource) {
+            Events.testEnd(((ClassSource) source).getClassName(), "(class)", "skipped", 0, null);
+        }
+    }
+
+    @Override
+    public void executionFinished(TestIdentifier id, TestExecutionResult result) {
+        Long start = starts.remove(id.getUniqueId());
+        long millis = start == null ? 0 : System.currentTimeMillis() - start;
+        TestSource source = id.getSource().orElse(null);
+        Throwable failure = result.getThrowable().orElse(null);
+        String status;
+        switch (result.getStatus()) {
+            case SUCCESSFUL:
+                status = "passed";
+                break;
+            case ABORTED:
+                status = "skipped";
+                break;
+            default:
+                status = Events.failureStatus(failure);
+        }
+        if (id.isTest() && source instanceof MethodSource) {
+            MethodSource method = (MethodSource) source;
+            Events.testEnd(method.getClassName(), name(id, method), status, millis, failure);
+        } else if (source instanceof ClassSource && result.getStatus() == TestExecutionResult.Status.FAILED) {
+            // @BeforeAll or @AfterAll failed
+            Events.testEnd(((ClassSource) source).getClassName(), "(class)", status, millis, failure);
+        }
+    }
+
+    /** the method name, with the display name for parameterized and repeated tests */
+    private static String name(TestIdentifier id, MethodSource source) {
+        String display = id.getDisplayName();
+        return display.startsWith("[") ? source.getMethodName() + display : source.getMethodName();
+    }
+}
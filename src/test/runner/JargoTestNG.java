@@ -0,0 +1,98 @@
+package jargo.runner;
+
+import java.util.ArrayList;
+import java.util.HashSet;
+import java.util.List;
+import java.util.Set;
+
+import org.testng.IMethodInstance;
+import org.testng.IMethodInterceptor;
+import org.testng.ITestContext;
+import org.testng.ITestListener;
+import org.testng.ITestNGListener;
+import org.testng.ITestResult;
+import org.testng.TestNG;
+
+/**
+ * Runs TestNG test classes and reports every test to jargo.
+ */
+public class JargoTestNG implements ITestListener {
+    private final Set<String> started = new HashSet<>();
+
+    public static void main(String[] args) throws Exception {
+        Selection selection = Selection.parse(args);
+        Events.captureOutput();
+        List<Class<?>> classes = new ArrayList<>();
+        for (String className : selection.classNames()) {
+            classes.add(Class.forName(className, false, JargoTestNG.class.getClassLoader()));
+        }
+        TestNG testng = new TestNG();
+        testng.setUseDefaultListeners(false);
+        testng.setVerbose(0);
+        testng.setTestClasses(classes.toArray(new Class<?>[0]));
+        // tags are TestNG groups
+        if (!selection.includeTags.isEmpty()) {
+            testng.setGroups(String.join(",", selection.includeTags));
+        }
+        if (!selection.excludeTags.isEmpty()) {
+            testng.setExcludedGroups(String.join(",", selection.excludeTags));
+        }
+        if (selection.hasMethodPatterns()) {
+            testng.addListener((ITestNGListener) (IMethodInterceptor) (methods, context) -> {
+                List<IMethodInstance> selected = new ArrayList<>();
+                for (IMethodInstance method : methods) {
+                    String className = method.getMethod().getTestClass().getName();
+                    if (selection.matches(className, method.getMethod().getMethodName())) {
+                        selected.add(method);
+                    }
+                }
+                return selected;
+            });
+        }
+        testng.addListener((ITestNGListener) new JargoTestNG());
+        testng.run();
+        System.exit(0);
+    }
+
+    @Override
+    public synchronized void onTestStart(ITestResult result) {
+        String className = result.getTestClass().getName();
+        if (started.add(className)) {
+            Events.classStart(className);
+        }
+    }
+
+    @Override
+    public void onTestSuccess(ITestResult result) {
+        end(result, "passed");
+    }
+
+    @Override
+    public void onTestFailure(ITestResult result) {
+        end(result, Events.failureStatus(result.getThrowable()));
+    }
+
+    @Override
+    public void onTestSkipped(ITestResult result) {
+        end(result, "skipped");
+    }
+
+    @Override
+    public void onTestFailedButWithinSuccessPercentage(ITestResult result) {
+        end(result, "passed");
+    }
+
+    @Override
+    public void onStart(ITestContext context) {
+    }
+
+    @Override
+    public void onFinish(ITestContext context) {
+    }
+
+    private static void end(ITestResult result, String status) {
+        Throwable failure = "passed".equals(status) || "skipped".equals(status) ? null : result.getThrowable();
+        Events.testEnd(result.getTestClass().getName(), result.getMethod().getMethodName(), status,
+                result.getEndMillis() - result.getStartMillis(), failure);
+    }
+}
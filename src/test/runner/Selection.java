@@ -0,0 +1,121 @@
+package jargo.runner;
+
+import java.lang.annotation.Annotation;
+import java.lang.reflect.Method;
+import java.util.ArrayList;
+import java.util.LinkedHashMap;
+import java.util.List;
+import java.util.Map;
+import java.util.regex.Pattern;
+
+/**
+ * The tests jargo asks to run: [--include-tag tag]... [--exclude-tag tag]... class[#method-glob]...
+ */
+public final class Selection {
+    final List<String> includeTags = new ArrayList<>();
+    final List<String> excludeTags = new ArrayList<>();
+    /** per class the method patterns, an empty list selects all methods */
+    private final Map<String, List<Pattern>> classes = new LinkedHashMap<>();
+
+    public static Selection parse(String[] args) {
+        Selection selection = new Selection();
+        for (int i = 0; i < args.length; i++) {
+            if ("--include-tag".equals(args[i])) {
+                selection.includeTags.add(args[++i]);
+            } else if ("--exclude-tag".equals(args[i])) {
+                selection.excludeTags.add(args[++i]);
+            } else {
+                int hash = args[i].indexOf('#');
+                String className = hash < 0 ? args[i] : args[i].substring(0, hash);
+                List<Pattern> methods = selection.classes.computeIfAbsent(className, c -> new ArrayList<>());
+                if (hash >= 0) {
+                    methods.add(glob(args[i].substring(hash + 1)));
+                }
+            }
+        }
+        return selection;
+    }
+
+    public Iterable<String> classNames() {
+        return classes.keySet();
+    }
+
+    public boolean hasTags() {
+        return !includeTags.isEmpty() || !excludeTags.isEmpty();
+    }
+
+    public boolean hasMethodPatterns() {
+        for (List<Pattern> methods : classes.values()) {
+            if (!methods.isEmpty()) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    public boolean matches(String className, String methodName) {
+        List<Pattern> methods = classes.get(className);
+        if (methods == null) {
+            return false;
+        }
+        if (methods.isEmpty()) {
+            return true;
+        }
+        for (Pattern method : methods) {
+            if (method.matcher(methodName).matches()) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /** tags match on simple or fully qualified names, so that JUnit 4 categories (classes) can be selected */
+    public boolean matchesTags(List<String> tags) {
+        if (!includeTags.isEmpty() && !containsAny(tags, includeTags)) {
+            return false;
+        }
+        return !containsAny(tags, excludeTags);
+    }
+
+    private static boolean containsAny(List<String> tags, List<String> wanted) {
+        for (String tag : tags) {
+            String simpleName = tag.substring(Math.max(tag.lastIndexOf('.'), tag.lastIndexOf('$')) + 1);
+            if (wanted.contains(tag) || wanted.contains(simpleName)) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /** the names of the classes in a JUnit 4 Category annotation, found by name so that older JUnit versions work */
+    public static List<String> categories(Annotation[] annotations) {
+        List<String> categories = new ArrayList<>();
+        for (Annotation annotation : annotations) {
+            if ("org.junit.experimental.categories.Category".equals(annotation.annotationType().getName())) {
+                try {
+                    Method value = annotation.annotationType().getMethod("value");
+                    for (Class<?> category : (Class<?>[]) value.invoke(annotation)) {
+                        categories.add(category.getName());
+                    }
+                } catch (ReflectiveOperationException e) {
+                    throw new IllegalStateException(e);
+                }
+            }
+        }
+        return categories;
+    }
+
+    private static Pattern glob(String glob) {
+        StringBuilder regex = new StringBuilder();
+        for (char c : glob.toCharArray()) {
+            if (c == '*') {
+                regex.append(".*");
+            } else if (c == '?') {
+                regex.append('.');
+            } else {
+                regex.append(Pattern.quote(String.valueOf(c)));
+            }
+        }
+        return Pattern.compile(regex.toString());
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Error;
+
+use crate::test::filter::Selector;
+use crate::test::TestResult;
+
+/// how long each test class took in previous runs, relative to the target directory
+const DURATIONS: &str = "jargo/test-durations";
+
+/// assumed for a class that hasn't run before, when there is nothing to take the average of
+const DEFAULT_DURATION: Duration = Duration::from_secs(1);
+
+/// Divides the test classes over at most `forks` JVMs, so that they all take about as long.
+/// The slowest classes are handed out first, each to the JVM with the least work so far.
+pub(super) fn balance(
+    selectors: &[Selector],
+    durations: &HashMap<String, Duration>,
+    forks: usize,
+) -> Vec<Vec<Selector>> {
+    let mut classes: BTreeMap<&str, Vec<Selector>> = BTreeMap::new();
+    for selector in selectors {
+        classes
+            .entry(&selector.class)
+            .or_default()
+            .push(selector.clone());
+    }
+    let unknown = if durations.is_empty() {
+        DEFAULT_DURATION
+    } else {
+        durations.values().sum::<Duration>() / durations.len() as u32
+    };
+    let mut classes: Vec<(Duration, Vec<Selector>)> = classes
+        .into_iter()
+        .map(|(class, selectors)| (*durations.get(class).unwrap_or(&unknown), selectors))
+        .collect();
+    // stable, so classes with the same duration stay in name order
+    classes.sort_by_key(|(duration, _)| std::cmp::Reverse(*duration));
+
+    let forks = forks.clamp(1, classes.len().max(1));
+    let mut buckets: Vec<(Duration, Vec<Selector>)> = vec![(Duration::ZERO, vec![]); forks];
+    for (duration, selectors) in classes {
+        if let Some(bucket) = buckets.iter_mut().min_by_key(|(total, _)| *total) {
+            bucket.0 += duration;
+            bucket.1.extend(selectors);
+        }
+    }
+    buckets
+        .into_iter()
+        .map(|(_, selectors)| selectors)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+pub(super) fn read_durations(target: &Path) -> Result<HashMap<String, Duration>, Error> {
+    let path = target.join(DURATIONS);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| {
+            let (class, millis) = line.split_once(' ')?;
+            Some((
+                class.to_owned(),
+                Duration::from_millis(millis.parse().ok()?),
+            ))
+        })
+        .collect())
+}
+
+/// Keeps the duration of every class that ran, as the sum of its tests.
+/// Classes that didn't run this time keep their old duration.
+pub(super) fn store_durations(target: &Path, results: &[TestResult]) -> Result<(), Error> {
+    let mut ran: HashMap<&str, Duration> = HashMap::new();
+    for result in results {
+        *ran.entry(&result.class).or_default() += result.duration;
+    }
+    let mut durations: BTreeMap<String, Duration> = read_durations(target)?.into_iter().collect();
+    for (class, duration) in ran {
+        durations.insert(class.to_owned(), duration);
+    }
+
+    let path = target.join(DURATIONS);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents: String = durations
+        .into_iter()
+        .map(|(class, duration)| format!("{} {}\n", class, duration.as_millis()))
+        .collect();
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crate::test::filter::Selector;
+    use crate::test::schedule::balance;
+
+    fn selector(class: &str) -> Selector {
+        Selector {
+            class: class.into(),
+            method: None,
+        }
+    }
+
+    #[test]
+    fn balance_by_duration() {
+        let selectors: Vec<Selector> = ["A", "B", "C", "D", "E"]
+            .iter()
+            .map(|c| selector(c))
+            .collect();
+        let durations: HashMap<String, Duration> = [("A", 10), ("B", 6), ("C", 5), ("D", 3)]
+            .iter()
+            .map(|(c, s)| (c.to_string(), Duration::from_secs(*s)))
+            .collect();
+
+        // E is unknown and counts as the average of 6s
+        let buckets = balance(&selectors, &durations, 2);
+        let classes: Vec<Vec<&str>> = buckets
+            .iter()
+            .map(|b| b.iter().map(|s| s.class.as_str()).collect())
+            .collect();
+        assert_eq!(classes, vec![vec!["A", "C"], vec!["B", "E", "D"]]);
+
+        assert_eq!(balance(&selectors, &durations, 10).len(), 5);
+        assert_eq!(balance(&selectors, &durations, 1).len(), 1);
+    }
+}
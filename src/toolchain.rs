@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Error};
+
+use crate::config::config;
+
+/// An installed JDK
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jdk {
+    pub home: PathBuf,
+    /// JAVA_VERSION from the release file, eg. 17.0.15 or 1.8.0_402
+    pub version: String,
+    /// IMPLEMENTOR from the release file, eg. Eclipse Adoptium
+    pub vendor: Option<String>,
+}
+
+/// The JDK a project asks for, with `java = "21"` in [package] or [toolchain]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaRequest {
+    /// a feature release like 21, or a more precise version like 17.0.2
+    pub version: String,
+    /// part of the vendor or the directory name, eg. "temurin", case insensitive
+    pub vendor: Option<String>,
+}
+
+impl Jdk {
+    /// reads the release file in the JDK home, None when it isn't a JDK
+    pub fn read(home: &Path) -> Option<Self> {
+        if !home.join("bin").join(executable("javac")).exists() {
+            return None;
+        }
+        let release = parse_release(&fs::read_to_string(home.join("release")).ok()?);
+        Some(Jdk {
+            home: home.to_path_buf(),
+            version: release.get("JAVA_VERSION")?.clone(),
+            vendor: release.get("IMPLEMENTOR").cloned(),
+        })
+    }
+
+    /// the feature release, 8 for 1.8.0_402 and 21 for 21.0.2
+    pub fn feature(&self) -> u32 {
+        let mut parts = self.version.split(['.', '_', '-', '+']);
+        match parts.next() {
+            Some("1") => parts.next(),
+            first => first,
+        }
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default()
+    }
+
+    pub fn javac(&self) -> PathBuf {
+        self.home.join("bin").join(executable("javac"))
+    }
+
+    pub fn java(&self) -> PathBuf {
+        self.home.join("bin").join(executable("java"))
+    }
+
+    fn matches(&self, request: &JavaRequest) -> bool {
+        let version_matches = match request.version.parse::<u32>() {
+            Ok(feature) => self.feature() == feature,
+            Err(_) => {
+                self.version == request.version
+                    || self
+                        .version
+                        .strip_prefix(&request.version)
+                        .is_some_and(|rest| rest.starts_with(['.', '_']))
+            }
+        };
+        let vendor_matches = match &request.vendor {
+            Some(vendor) => {
+                let vendor = vendor.to_lowercase();
+                let dir_name = self
+                    .home
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                dir_name.contains(&vendor)
+                    || self
+                        .vendor
+                        .as_ref()
+                        .is_some_and(|v| v.to_lowercase().contains(&vendor))
+            }
+            None => true,
+        };
+        version_matches && vendor_matches
+    }
+}
+
+/// Finds the installed JDKs in JAVA_HOME, /usr/lib/jvm, ~/.sdkman/candidates/java and ~/.jdks.
+/// JAVA_HOME comes first, the others are ordered from the newest version to the oldest.
+pub fn discover() -> Vec<Jdk> {
+    let java_home = std::env::var_os("JAVA_HOME")
+        .and_then(|home| fs::canonicalize(home).ok())
+        .and_then(|home| Jdk::read(&home));
+
+    let user_home = PathBuf::from(&config().user_home);
+    let mut homes = vec![];
+    for dir in [
+        PathBuf::from("/usr/lib/jvm"),
+        user_home.join(".sdkman/candidates/java"),
+        user_home.join(".jdks"),
+    ] {
+        if let Ok(entries) = fs::read_dir(dir) {
+            homes.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()));
+        }
+    }
+    // the same JDK is often linked under several names, like default-java or sdkman's current
+    let mut homes: Vec<PathBuf> = homes
+        .into_iter()
+        .filter_map(|home| fs::canonicalize(home).ok())
+        .filter(|home| java_home.as_ref().map(|jdk| &jdk.home) != Some(home))
+        .collect();
+    homes.sort();
+    homes.dedup();
+
+    let mut jdks: Vec<Jdk> = homes.iter().filter_map(|home| Jdk::read(home)).collect();
+    jdks.sort_by_key(|jdk| std::cmp::Reverse(jdk.feature()));
+    java_home.into_iter().chain(jdks).collect()
+}
+
+/// picks the first installed JDK that matches the request
+pub fn select(request: &JavaRequest) -> Result<Jdk, Error> {
+    let jdks = discover();
+    if let Some(jdk) = jdks.iter().find(|jdk| jdk.matches(request)) {
+        return Ok(jdk.clone());
+    }
+    let wanted = match &request.vendor {
+        Some(vendor) => format!("{} ({})", request.version, vendor),
+        None => request.version.clone(),
+    };
+    let found = if jdks.is_empty() {
+        "none".to_owned()
+    } else {
+        jdks.iter()
+            .map(|jdk| format!("{} in {}", jdk.version, jdk.home.display()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Err(anyhow!(
+        "no JDK found for java {}, found: {}. Install it in one of the known locations or set JAVA_HOME to it",
+        wanted,
+        found
+    ))
+}
+
+/// the KEY="value" lines of the release file
+fn parse_release(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((
+                key.trim().to_owned(),
+                value.trim().trim_matches('"').to_owned(),
+            ))
+        })
+        .collect()
+}
+
+fn executable(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::toolchain::{parse_release, JavaRequest, Jdk};
+
+    fn jdk(dir: &str, version: &str, vendor: &str) -> Jdk {
+        Jdk {
+            home: PathBuf::from("/usr/lib/jvm").join(dir),
+            version: version.into(),
+            vendor: Some(vendor.into()),
+        }
+    }
+
+    fn request(version: &str, vendor: Option<&str>) -> JavaRequest {
+        JavaRequest {
+            version: version.into(),
+            vendor: vendor.map(String::from),
+        }
+    }
+
+    #[test]
+    fn release_file() {
+        let release = parse_release("IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"21.0.2\"\n");
+        assert_eq!(release["IMPLEMENTOR"], "Eclipse Adoptium");
+        assert_eq!(release["JAVA_VERSION"], "21.0.2");
+    }
+
+    #[test]
+    fn match_versions() {
+        let temurin = jdk("temurin-21.jdk", "21.0.2", "Eclipse Adoptium");
+        let java8 = jdk("java-8-openjdk", "1.8.0_402", "Private Build");
+        assert_eq!(temurin.feature(), 21);
+        assert_eq!(java8.feature(), 8);
+
+        assert!(temurin.matches(&request("21", None)));
+        assert!(temurin.matches(&request("21.0", None)));
+        assert!(temurin.matches(&request("21.0.2", None)));
+        assert!(!temurin.matches(&request("21.0.20", None)));
+        assert!(!temurin.matches(&request("17", None)));
+        assert!(temurin.matches(&request("21", Some("Temurin"))));
+        assert!(temurin.matches(&request("21", Some("adoptium"))));
+        assert!(!temurin.matches(&request("21", Some("zulu"))));
+        assert!(java8.matches(&request("8", None)));
+        assert!(java8.matches(&request("1.8", None)));
+    }
+}
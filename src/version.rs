@@ -0,0 +1,742 @@
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+
+/// A version of an artifact, ordered like maven does it (ComparableVersion):
+/// 1.0-alpha1 < 1.0-beta < 1.0-rc1 < 1.0-SNAPSHOT < 1.0 = 1.0.0 = 1.0-ga < 1.0-sp1 < 1.0.1
+///
+/// - dots separate numbers, dashes and a switch between digits and letters start a sublist
+/// - missing numbers are zeros, so 1 = 1.0 = 1.0.0
+/// - qualifiers are case-insensitive, final, ga and release are the release itself and
+///   a1, b1 and m1 are alpha-1, beta-1 and milestone-1
+/// - unknown qualifiers come after the release and sp, in alphabetical order
+///
+/// Any string is a version, it keeps the original for display.
+#[derive(Debug, Clone)]
+pub struct Version {
+    value: String,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    /// digits without leading zeros, so that large numbers can be compared too
+    Int(String),
+    /// a qualifier, lowercase and without aliases
+    Str(String),
+    /// what comes after a dash, or after a switch between digits and letters
+    List(Vec<Item>),
+}
+
+/// the known qualifiers, in their order. The empty one is the release itself.
+const QUALIFIERS: [&str; 7] = ["alpha", "beta", "milestone", "rc", "snapshot", "", "sp"];
+const RELEASE: usize = 5;
+
+impl Version {
+    pub fn new(version: &str) -> Self {
+        Self {
+            value: version.into(),
+            items: parse(&version.to_lowercase()),
+        }
+    }
+
+    /// the version as it was written
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// the normalized form, equal for versions that are equal, like 1 for 1.0.0-GA
+    pub fn canonical(&self) -> String {
+        canonical(&self.items)
+    }
+
+    /// like 1.0-SNAPSHOT, in any case
+    pub fn is_snapshot(&self) -> bool {
+        let suffix = b"-SNAPSHOT";
+        let value = self.value.as_bytes();
+        value.len() >= suffix.len()
+            && value[value.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+    }
+
+    /// has a qualifier before the release, like alpha, rc or SNAPSHOT
+    pub fn is_pre_release(&self) -> bool {
+        self.qualifiers().any(|q| {
+            QUALIFIERS
+                .iter()
+                .position(|k| *k == q)
+                .is_some_and(|i| i < RELEASE)
+        })
+    }
+
+    fn qualifiers(&self) -> impl Iterator<Item = &str> {
+        let mut stack: Vec<&Item> = self.items.iter().collect();
+        std::iter::from_fn(move || {
+            while let Some(item) = stack.pop() {
+                match item {
+                    Item::Str(qualifier) => return Some(qualifier.as_str()),
+                    Item::List(items) => stack.extend(items),
+                    Item::Int(_) => {}
+                }
+            }
+            None
+        })
+    }
+}
+
+/// like maven, a sublist starts with a dash
+fn canonical(items: &[Item]) -> String {
+    let mut canonical = String::new();
+    for item in items {
+        if !canonical.is_empty() {
+            canonical.push(if matches!(item, Item::List(_)) {
+                '-'
+            } else {
+                '.'
+            });
+        }
+        match item {
+            Item::Int(value) | Item::Str(value) => canonical.push_str(value),
+            Item::List(items) => canonical.push_str(&self::canonical(items)),
+        }
+    }
+    canonical
+}
+
+/// like maven: a dot separates items, a dash starts a sublist and so does a switch between
+/// digits and letters. Trailing zeros and release qualifiers are removed from every list.
+fn parse(version: &str) -> Vec<Item> {
+    let mut stack: Vec<Vec<Item>> = vec![vec![]];
+    let mut is_digit = false;
+    let mut start = 0;
+    for (i, c) in version.char_indices() {
+        let list = stack.last_mut().unwrap();
+        match c {
+            '.' | '-' => {
+                if i == start {
+                    list.push(Item::Int("0".into()));
+                } else {
+                    list.push(item(is_digit, &version[start..i], false));
+                }
+                start = i + 1;
+                if c == '-' {
+                    stack.push(vec![]);
+                }
+            }
+            c if c.is_ascii_digit() => {
+                if !is_digit && i > start {
+                    list.push(item(false, &version[start..i], true));
+                    start = i;
+                    stack.push(vec![]);
+                }
+                is_digit = true;
+            }
+            _ => {
+                if is_digit && i > start {
+                    list.push(item(true, &version[start..i], false));
+                    start = i;
+                    stack.push(vec![]);
+                }
+                is_digit = false;
+            }
+        }
+    }
+    if version.len() > start {
+        // a qualifier at the end after a dot is one after a dash, like in 2.0.a = 2.0-a
+        if !is_digit && !stack.last().unwrap().is_empty() {
+            stack.push(vec![]);
+        }
+        let list = stack.last_mut().unwrap();
+        list.push(item(is_digit, &version[start..], false));
+    }
+    // every new list was the last item of the one before it
+    while stack.len() > 1 {
+        let mut list = stack.pop().unwrap();
+        normalize(&mut list);
+        stack.last_mut().unwrap().push(Item::List(list));
+    }
+    let mut items = stack.pop().unwrap();
+    normalize(&mut items);
+    items
+}
+
+fn item(is_digit: bool, value: &str, followed_by_digit: bool) -> Item {
+    if is_digit {
+        let digits = value.trim_start_matches('0');
+        return Item::Int(if digits.is_empty() { "0" } else { digits }.into());
+    }
+    let value = match value {
+        "a" if followed_by_digit => "alpha",
+        "b" if followed_by_digit => "beta",
+        "m" if followed_by_digit => "milestone",
+        "ga" | "final" | "release" => "",
+        "cr" => "rc",
+        _ => value,
+    };
+    Item::Str(value.into())
+}
+
+fn normalize(items: &mut Vec<Item>) {
+    for i in (0..items.len()).rev() {
+        if items[i].is_null() {
+            items.remove(i);
+        } else if !matches!(items[i], Item::List(_)) {
+            break;
+        }
+    }
+}
+
+/// the position of a qualifier, unknown ones come after sp, in alphabetical order
+fn comparable_qualifier(qualifier: &str) -> String {
+    match QUALIFIERS.iter().position(|q| *q == qualifier) {
+        Some(index) => index.to_string(),
+        None => format!("{}-{}", QUALIFIERS.len(), qualifier),
+    }
+}
+
+impl Item {
+    fn is_null(&self) -> bool {
+        match self {
+            Item::Int(value) => value == "0",
+            Item::Str(value) => value.is_empty(),
+            Item::List(items) => items.is_empty(),
+        }
+    }
+
+    /// None is padding, like the missing .0 in 1.0 compared to 1.0.0
+    fn compare(&self, other: Option<&Item>) -> Ordering {
+        match (self, other) {
+            (Item::Int(value), None) => {
+                if value == "0" {
+                    Ordering::Equal
+                } else {
+                    Ordering::Greater
+                }
+            }
+            (Item::Int(value), Some(Item::Int(other))) => {
+                value.len().cmp(&other.len()).then_with(|| value.cmp(other))
+            }
+            (Item::Int(_), Some(_)) => Ordering::Greater,
+            (Item::Str(value), None) => comparable_qualifier(value).cmp(&comparable_qualifier("")),
+            (Item::Str(value), Some(Item::Str(other))) => {
+                comparable_qualifier(value).cmp(&comparable_qualifier(other))
+            }
+            (Item::Str(_), Some(_)) => Ordering::Less,
+            (Item::List(items), None) => items
+                .iter()
+                .map(|item| item.compare(None))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal),
+            (Item::List(_), Some(Item::Int(_))) => Ordering::Less,
+            (Item::List(_), Some(Item::Str(_))) => Ordering::Greater,
+            (Item::List(items), Some(Item::List(other))) => compare_lists(items, other),
+        }
+    }
+}
+
+fn compare_lists(left: &[Item], right: &[Item]) -> Ordering {
+    for i in 0..left.len().max(right.len()) {
+        let result = match (left.get(i), right.get(i)) {
+            (Some(l), r) => l.compare(r),
+            (None, Some(r)) => r.compare(None).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if result.is_ne() {
+            return result;
+        }
+    }
+    Ordering::Equal
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_lists(&self.items, &other.items)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl From<&str> for Version {
+    fn from(version: &str) -> Self {
+        Self::new(version)
+    }
+}
+
+impl FromStr for Version {
+    type Err = Infallible;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(version))
+    }
+}
+
+/// A version requirement that is resolved against the versions in maven-metadata.xml.
+///
+/// Maven ranges: `[1.2,2.0)`, `(,1.5]`, `[1.0]` or more of them, like `[1.0,2.0),[3.0,)`.
+/// Cargo-like requirements: `^1.2` (>= 1.2, < 2), `~1.2.3` (>= 1.2.3, < 1.3) and `1.*`.
+/// Snapshots only match when the requirement has one, pre-releases never match a cargo-like
+/// requirement without one.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct VersionRange {
+    restrictions: Vec<Restriction>,
+    pre_releases: bool,
+    snapshots: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Restriction {
+    /// the bound and whether it is inclusive, None is unbounded
+    lower: Option<(Version, bool)>,
+    upper: Option<(Version, bool)>,
+}
+
+impl Restriction {
+    fn contains(&self, version: &Version) -> bool {
+        let above = match &self.lower {
+            Some((lower, inclusive)) => version > lower || (*inclusive && version == lower),
+            None => true,
+        };
+        let below = match &self.upper {
+            Some((upper, inclusive)) => version < upper || (*inclusive && version == upper),
+            None => true,
+        };
+        above && below
+    }
+
+    fn bounds(&self) -> impl Iterator<Item = &Version> {
+        self.lower.iter().chain(self.upper.iter()).map(|(v, _)| v)
+    }
+}
+
+impl VersionRange {
+    /// a version that is not a plain one, but a range or requirement
+    pub(crate) fn is_range(version: &str) -> bool {
+        version.starts_with(['[', '(', '^', '~']) || version.ends_with('*')
+    }
+
+    pub(crate) fn parse(range: &str) -> Result<Self, Error> {
+        let range = range.trim();
+        let restrictions = match range.chars().next() {
+            Some('[') | Some('(') => parse_maven(range)?,
+            Some('^') => vec![cargo_like(range, &range[1..], Bump::FirstNonZero)?],
+            Some('~') => vec![cargo_like(range, &range[1..], Bump::Minor)?],
+            _ => match range.strip_suffix('*') {
+                Some("") => vec![Restriction {
+                    lower: None,
+                    upper: None,
+                }],
+                Some(prefix) => match prefix.strip_suffix('.') {
+                    Some(prefix) => vec![cargo_like(range, prefix, Bump::Last)?],
+                    None => return Err(invalid(range)),
+                },
+                None => return Err(invalid(range)),
+            },
+        };
+        let maven = range.starts_with(['[', '(']);
+        let bounds = || restrictions.iter().flat_map(Restriction::bounds);
+        let pre_releases = maven || bounds().any(Version::is_pre_release);
+        let snapshots = bounds().any(Version::is_snapshot);
+        Ok(Self {
+            restrictions,
+            pre_releases,
+            snapshots,
+        })
+    }
+
+    pub(crate) fn contains(&self, version: &Version) -> bool {
+        if version.is_snapshot() && !self.snapshots {
+            return false;
+        }
+        if version.is_pre_release() && !version.is_snapshot() && !self.pre_releases {
+            return false;
+        }
+        self.restrictions.iter().any(|r| r.contains(version))
+    }
+
+    /// the highest of the versions that is in the range
+    pub(crate) fn select<'a>(
+        &self,
+        versions: impl IntoIterator<Item = &'a str>,
+    ) -> Option<&'a str> {
+        versions
+            .into_iter()
+            .map(|v| (Version::new(v), v))
+            .filter(|(version, _)| self.contains(version))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, v)| v)
+    }
+}
+
+fn invalid(range: &str) -> Error {
+    anyhow!(
+        "invalid version range {}, use eg. [1.0,2.0), ^1.2, ~1.2.3 or 1.*",
+        range
+    )
+}
+
+/// one or more maven ranges, separated by commas
+fn parse_maven(range: &str) -> Result<Vec<Restriction>, Error> {
+    let mut restrictions = vec![];
+    let mut rest = range;
+    while !rest.is_empty() {
+        let lower_inclusive = match rest.chars().next() {
+            Some('[') => true,
+            Some('(') => false,
+            _ => return Err(invalid(range)),
+        };
+        let end = rest.find([']', ')']).ok_or_else(|| invalid(range))?;
+        let upper_inclusive = &rest[end..=end] == "]";
+        let bound = |version: &str, inclusive: bool| {
+            let version = version.trim();
+            (!version.is_empty()).then(|| (Version::new(version), inclusive))
+        };
+        let restriction = match rest[1..end].split_once(',') {
+            // [1.0] is exactly 1.0
+            None => {
+                let version = bound(&rest[1..end], true).ok_or_else(|| invalid(range))?;
+                if !lower_inclusive || !upper_inclusive {
+                    return Err(invalid(range));
+                }
+                Restriction {
+                    lower: Some(version.clone()),
+                    upper: Some(version),
+                }
+            }
+            Some((lower, upper)) => Restriction {
+                lower: bound(lower, lower_inclusive),
+                upper: bound(upper, upper_inclusive),
+            },
+        };
+        if let (Some((lower, _)), Some((upper, _))) = (&restriction.lower, &restriction.upper) {
+            if lower > upper {
+                return Err(invalid(range));
+            }
+        }
+        restrictions.push(restriction);
+        rest = rest[end + 1..].trim_start();
+        if let Some(next) = rest.strip_prefix(',') {
+            rest = next.trim_start();
+            if rest.is_empty() {
+                return Err(invalid(range));
+            }
+        }
+    }
+    Ok(restrictions)
+}
+
+/// which number of a cargo-like requirement is raised for the upper bound
+enum Bump {
+    /// ^: the major, or the minor for 0.x, and so on
+    FirstNonZero,
+    /// ~: the minor, or the major when there is only one
+    Minor,
+    /// 1.2.*: the last one given
+    Last,
+}
+
+fn cargo_like(range: &str, version: &str, bump: Bump) -> Result<Restriction, Error> {
+    let (numbers, qualifier) = match version.split_once('-') {
+        Some((numbers, qualifier)) => (numbers, Some(qualifier)),
+        None => (version, None),
+    };
+    let mut numbers = numbers
+        .split('.')
+        .map(|n| n.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid(range))?;
+    if qualifier.is_some_and(str::is_empty) {
+        return Err(invalid(range));
+    }
+    let index = match bump {
+        Bump::FirstNonZero => numbers
+            .iter()
+            .position(|n| *n != 0)
+            .unwrap_or(numbers.len() - 1),
+        Bump::Minor => 1.min(numbers.len() - 1),
+        Bump::Last => numbers.len() - 1,
+    };
+    numbers.truncate(index + 1);
+    numbers[index] += 1;
+    let upper = numbers
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".");
+    Ok(Restriction {
+        lower: Some((Version::new(version), true)),
+        upper: Some((Version::new(&upper), false)),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::version::{Version, VersionRange};
+
+    /// every version is lower than the ones after it, the tables of maven's ComparableVersionTest
+    fn assert_ordered(versions: &[&str]) {
+        for (i, low) in versions.iter().enumerate() {
+            for high in &versions[i + 1..] {
+                assert!(Version::new(low) < Version::new(high), "{} < {}", low, high);
+                assert!(Version::new(high) > Version::new(low), "{} > {}", high, low);
+            }
+        }
+    }
+
+    #[test]
+    fn qualifiers_in_order() {
+        assert_ordered(&[
+            "1-alpha2snapshot",
+            "1-alpha2",
+            "1-alpha-123",
+            "1-beta-2",
+            "1-beta123",
+            "1-m2",
+            "1-m11",
+            "1-rc",
+            "1-cr2",
+            "1-rc123",
+            "1-SNAPSHOT",
+            "1",
+            "1-sp",
+            "1-sp2",
+            "1-sp123",
+            "1-abc",
+            "1-def",
+            "1-pom-1",
+            "1-1-snapshot",
+            "1-1",
+            "1-2",
+            "1-123",
+        ]);
+    }
+
+    #[test]
+    fn numbers_in_order() {
+        assert_ordered(&[
+            "2.0", "2.0.a", "2-1", "2.0.2", "2.0.123", "2.1.0", "2.1-a", "2.1b", "2.1-c", "2.1-1",
+            "2.1.0.1", "2.2", "2.123", "11.a2", "11.a11", "11.b2", "11.b11", "11.m2", "11.m11",
+            "11", "11.a", "11b", "11c", "11m",
+        ]);
+    }
+
+    #[test]
+    fn comparisons() {
+        let lower = [
+            ("1", "2"),
+            ("1.5", "2"),
+            ("1", "2.5"),
+            ("1.0", "1.1"),
+            ("1.1", "1.2"),
+            ("1.0.0", "1.1"),
+            ("1.0.1", "1.1"),
+            ("1.1", "1.2.0"),
+            ("1.0-alpha-1", "1.0"),
+            ("1.0-alpha-1", "1.0-alpha-2"),
+            ("1.0-alpha-1", "1.0-beta-1"),
+            ("1.0-beta-1", "1.0-SNAPSHOT"),
+            ("1.0-SNAPSHOT", "1.0"),
+            ("1.0-alpha-1-SNAPSHOT", "1.0-alpha-1"),
+            ("1.0", "1.0-1"),
+            ("1.0-1", "1.0-2"),
+            ("1.0.0", "1.0-1"),
+            ("2.0-1", "2.0.1"),
+            ("2.0.1-klm", "2.0.1-lmn"),
+            ("2.0.1", "2.0.1-xyz"),
+            ("2.0.1", "2.0.1-123"),
+            ("2.0.1-xyz", "2.0.1-123"),
+            // leading zeros
+            ("0.7", "2"),
+            ("0.2", "1.0.7"),
+            ("1.007", "1.8"),
+            // numbers that don't fit in an integer, or a long
+            ("1", "2147483648"),
+            ("2147483647", "2147483648"),
+            ("9223372036854775807", "9223372036854775808"),
+            ("1.9223372036854775807", "1.18446744073709551616"),
+            (
+                "99999999999999999999999999999999",
+                "100000000000000000000000000000000",
+            ),
+            // transitive, see MNG-5568
+            ("6.1.0rc3", "6.1.0"),
+            ("6.1.0rc3", "6.1H.5-beta"),
+            ("6.1.0", "6.1H.5-beta"),
+        ];
+        for (low, high) in lower {
+            assert!(Version::new(low) < Version::new(high), "{} < {}", low, high);
+            assert!(Version::new(high) > Version::new(low), "{} > {}", high, low);
+        }
+    }
+
+    #[test]
+    fn equal_versions() {
+        let equal = [
+            // padding
+            ("1", "1"),
+            ("1", "1.0"),
+            ("1", "1.0.0"),
+            ("1.0", "1.0.0"),
+            ("1", "1-0"),
+            ("1", "1.0-0"),
+            ("1.0", "1.0-0"),
+            ("0.0.0", "0"),
+            ("1.007", "1.7"),
+            // no separator between number and character
+            ("1a", "1-a"),
+            ("1a", "1.0-a"),
+            ("1a", "1.0.0-a"),
+            ("1.0a", "1-a"),
+            ("1.0.0a", "1-a"),
+            ("1x", "1-x"),
+            ("1x", "1.0-x"),
+            ("1x", "1.0.0-x"),
+            ("1.0x", "1-x"),
+            ("1.0.0x", "1-x"),
+            ("2.0.a", "2.0-a"),
+            ("2.0.Final", "2"),
+            // aliases
+            ("1ga", "1"),
+            ("1release", "1"),
+            ("1final", "1"),
+            ("1.5.Final", "1.5"),
+            ("1cr", "1rc"),
+            // a, b and m for alpha, beta and milestone, only when followed by a number
+            ("1a1", "1-alpha-1"),
+            ("1b2", "1-beta-2"),
+            ("1m3", "1-milestone-3"),
+            // case-insensitive
+            ("1X", "1x"),
+            ("1A", "1a"),
+            ("1B", "1b"),
+            ("1M", "1m"),
+            ("1Ga", "1"),
+            ("1GA", "1"),
+            ("1RELEASE", "1"),
+            ("1RELeaSE", "1"),
+            ("1Final", "1"),
+            ("1FinaL", "1"),
+            ("1FINAL", "1"),
+            ("1Cr", "1Rc"),
+            ("1cR", "1rC"),
+            ("1m3", "1Milestone3"),
+            ("1m3", "1MileStone3"),
+            ("1m3", "1MILESTONE3"),
+            ("1.0-SNAPSHOT", "1.0-snapshot"),
+        ];
+        for (a, b) in equal {
+            let (a, b) = (Version::new(a), Version::new(b));
+            assert_eq!(a, b, "{} = {}", a, b);
+            assert_eq!(a.canonical(), b.canonical(), "{} = {}", a, b);
+        }
+        assert_ne!(Version::new("1a"), Version::new("1-alpha"));
+        let versions: HashSet<Version> = ["1", "1.0", "1.0.0-GA", "1.1"]
+            .into_iter()
+            .map(Version::new)
+            .collect();
+        assert_eq!(versions.len(), 2);
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let canonical = [
+            ("1.0.0", "1"),
+            ("1.0-GA", "1"),
+            ("1.0-alpha1", "1-alpha-1"),
+            ("1.0a1-SNAPSHOT", "1-alpha-1-snapshot"),
+            ("1.0-RC2", "1-rc-2"),
+            ("2.0.1-xyz", "2.0.1-xyz"),
+            ("2.1-1", "2.1-1"),
+            ("1.0-sp-1", "1-sp-1"),
+            ("01.007", "1.7"),
+            ("6.1H.5-beta", "6.1-h.5-beta"),
+        ];
+        for (version, expected) in canonical {
+            let version = Version::new(version);
+            assert_eq!(version.canonical(), expected, "{}", version);
+            // the canonical form is a version too, with the same canonical form
+            assert_eq!(Version::new(&version.canonical()), version);
+        }
+    }
+
+    #[test]
+    fn kinds() {
+        let version = Version::new("1.0-SNAPSHOT");
+        assert!(version.is_snapshot());
+        assert!(version.is_pre_release());
+        assert!(Version::new("2.0.1-snapshot").is_snapshot());
+        assert!(!Version::new("1.0-20240101.120000-5").is_snapshot());
+        assert!(!Version::new("1.0-é").is_snapshot());
+        assert!(!Version::new("1.0-ééééé").is_snapshot());
+        assert!(Version::new("1.0-rc1").is_pre_release());
+        assert!(Version::new("1.0-M1").is_pre_release());
+        assert!(!Version::new("1.0-sp1").is_pre_release());
+        assert!(!Version::new("1.0.Final").is_pre_release());
+        assert!(!Version::new("1.0-jre").is_pre_release());
+        assert_eq!(Version::new("1.0.Final").to_string(), "1.0.Final");
+        assert_eq!("1.0".parse::<Version>().unwrap(), Version::new("1"));
+    }
+
+    #[test]
+    fn ranges() {
+        let versions = [
+            "0.9",
+            "1.0",
+            "1.2",
+            "1.5",
+            "1.9-rc1",
+            "2.0",
+            "2.1-SNAPSHOT",
+            "3.0",
+        ];
+        let select = |range: &str| VersionRange::parse(range).unwrap().select(versions);
+        assert_eq!(select("[1.2,2.0)"), Some("1.9-rc1"));
+        assert_eq!(select("(,1.5]"), Some("1.5"));
+        assert_eq!(select("(,1.5)"), Some("1.2"));
+        assert_eq!(select("[1.0]"), Some("1.0"));
+        assert_eq!(select("[1.0,2.0),[3.0,)"), Some("3.0"));
+        assert_eq!(select("[4,)"), None);
+        assert_eq!(select("^1.2"), Some("1.5"));
+        assert_eq!(select("^0.9"), Some("0.9"));
+        assert_eq!(select("~1.2.3"), None);
+        assert_eq!(select("~1"), Some("1.5"));
+        assert_eq!(select("1.*"), Some("1.5"));
+        assert_eq!(select("*"), Some("3.0"));
+        assert_eq!(select("[2.1-SNAPSHOT,)"), Some("3.0"));
+        assert_eq!(select("[2.0,2.1-SNAPSHOT]"), Some("2.1-SNAPSHOT"));
+
+        for invalid in ["[1.0", "(1.0)", "[2.0,1.0]", "^x", "1*", "[1.0],", "~"] {
+            assert!(VersionRange::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(VersionRange::is_range("^1.2"));
+        assert!(!VersionRange::is_range("1.2.3"));
+    }
+}
@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Error};
+use colored::Colorize;
+use notify::event::EventKind;
+use notify::{Event, RecursiveMode, Watcher};
+
+use crate::compile::MessageFormat;
+use crate::test::TestFilter;
+use crate::workspace::load_workspace;
+
+/// changes that come in this close after each other are handled together,
+/// like an IDE that saves all files or a git checkout
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// how long a program gets to stop after SIGTERM, before it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// how often is checked whether the program stopped by itself, while waiting for changes
+const POLL: Duration = Duration::from_millis(200);
+
+/// what to do after every change
+#[derive(Debug, Default, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum WatchAction {
+    /// compile the sources
+    #[default]
+    Build,
+    /// compile the sources and the tests, and run the tests
+    Test,
+    /// compile the sources and (re)start the main class
+    Run,
+}
+
+/// Builds, tests or runs the project and does it again every time something changes in src
+/// or in Jargo.toml, of the project or of the other projects in the workspace.
+/// A program started with `run` is stopped before the next build.
+///
+/// Never returns, except when watching fails. Build errors and test failures are shown, and then
+/// it waits for the next change.
+pub fn watch(
+    jargo_file: Option<&str>,
+    action: WatchAction,
+    member: Option<&str>,
+    message_format: MessageFormat,
+    refresh_snapshots: bool,
+    args: &[String],
+) -> Result<(), Error> {
+    let jargo_file = fs::canonicalize(jargo_file.unwrap_or("./Jargo.toml"))?;
+    let root = jargo_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    let mut watched = Watched::default();
+    watched.add(&mut watcher, &root)?;
+
+    loop {
+        let (mut program, projects) = match cycle(
+            &jargo_file,
+            action,
+            member,
+            message_format,
+            refresh_snapshots,
+            args,
+        ) {
+            Ok((program, projects)) => (program, projects),
+            Err(e) => {
+                println!("{} {}", "Error".red().bold(), e);
+                (None, vec![])
+            }
+        };
+        // a new member or path dependency is watched from now on
+        for project in projects {
+            watched.add(&mut watcher, &project)?;
+        }
+        println!(
+            "{} for changes in src and Jargo.toml",
+            "Watching".cyan().bold()
+        );
+        let changed = wait_for_changes(&events, &watched, &mut program)?;
+        if let Some(program) = program {
+            stop(program)?;
+        }
+        println!("{} {}", "Changed".cyan().bold(), describe(&changed, &root));
+    }
+}
+
+/// the directories of the projects that are watched
+#[derive(Debug, Default)]
+struct Watched {
+    projects: Vec<PathBuf>,
+}
+
+impl Watched {
+    fn add(&mut self, watcher: &mut impl Watcher, project: &Path) -> Result<(), Error> {
+        if self.projects.iter().any(|p| p == project) {
+            return Ok(());
+        }
+        // the directory and not the file, because editors often replace the file when saving
+        watcher.watch(project, RecursiveMode::NonRecursive)?;
+        let sources = project.join("src");
+        if sources.exists() {
+            watcher.watch(&sources, RecursiveMode::Recursive)?;
+        }
+        self.projects.push(project.to_path_buf());
+        Ok(())
+    }
+
+    /// anything in src, and the Jargo.toml of the projects
+    fn is_relevant(&self, path: &Path) -> bool {
+        self.projects
+            .iter()
+            .any(|p| path.starts_with(p.join("src")) || path == p.join("Jargo.toml"))
+    }
+}
+
+/// One build, with the test run or the started program.
+/// Also returns the directories of the projects in the workspace.
+fn cycle(
+    jargo_file: &Path,
+    action: WatchAction,
+    member: Option<&str>,
+    message_format: MessageFormat,
+    refresh_snapshots: bool,
+    args: &[String],
+) -> Result<(Option<Child>, Vec<PathBuf>), Error> {
+    // the manifests are read again, they may be among the changes
+    let mut workspace = load_workspace(jargo_file.to_str())?;
+    if refresh_snapshots {
+        workspace.refresh_snapshots();
+    }
+    let projects = workspace
+        .projects
+        .iter()
+        .map(|p| PathBuf::from(&p.project_root))
+        .collect();
+    let mut builds = workspace.build(message_format)?;
+    match action {
+        WatchAction::Build => Ok((None, projects)),
+        WatchAction::Test => {
+            for (i, project) in workspace.selected() {
+                let build = &mut builds[i];
+                crate::compile::run_tests(project, &build.classpath, &mut build.diagnostics)?;
+                crate::test::run(project, &build.classpath, &TestFilter::default())?;
+            }
+            Ok((None, projects))
+        }
+        WatchAction::Run => {
+            let i = workspace.runnable(member)?;
+            let program =
+                crate::run::command(&workspace.projects[i], &builds[i].classpath, None, args)?
+                    .spawn()
+                    .map_err(|e| anyhow!("failed to execute java: {}", e))?;
+            Ok((Some(program), projects))
+        }
+    }
+}
+
+/// Blocks until a file changed, and then until the changes stop coming in.
+/// Reports it when the program stops by itself in the meantime.
+fn wait_for_changes(
+    events: &Receiver<notify::Result<Event>>,
+    watched: &Watched,
+    program: &mut Option<Child>,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut changed = vec![];
+    let mut last_change: Option<Instant> = None;
+    loop {
+        let timeout = match last_change {
+            Some(last_change) => DEBOUNCE.saturating_sub(last_change.elapsed()),
+            None => POLL,
+        };
+        match events.recv_timeout(timeout) {
+            Ok(event) => {
+                let paths = relevant_paths(event?, watched);
+                if !paths.is_empty() {
+                    changed.extend(paths);
+                    last_change = Some(Instant::now());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if last_change.is_some() {
+                    changed.sort();
+                    changed.dedup();
+                    return Ok(changed);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(anyhow!("stopped watching")),
+        }
+        if let Some(child) = program {
+            if let Some(status) = child.try_wait()? {
+                println!("{} {}", "Stopped".cyan().bold(), status);
+                *program = None;
+            }
+        }
+    }
+}
+
+/// the files that were created, changed or removed, without the files that editors write
+/// next to the file that is edited, and without anything in the project root but Jargo.toml
+fn relevant_paths(event: Event, watched: &Watched) -> Vec<PathBuf> {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return vec![];
+    }
+    event
+        .paths
+        .into_iter()
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let is_temporary = name.ends_with('~')
+                || name.ends_with(".swp")
+                || name.ends_with(".swx")
+                || name.starts_with(".#")
+                || name == "4913";
+            !is_temporary && watched.is_relevant(path)
+        })
+        .collect()
+}
+
+/// Asks the program to stop, so that shutdown hooks run, and kills it when it takes too long
+fn stop(mut program: Child) -> Result<(), Error> {
+    #[cfg(unix)]
+    {
+        // SAFETY: kill only sends a signal to the process we started
+        unsafe {
+            libc::kill(program.id() as libc::pid_t, libc::SIGTERM);
+        }
+        let started = Instant::now();
+        while started.elapsed() < STOP_TIMEOUT {
+            if program.try_wait()?.is_some() {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+    program.kill()?;
+    program.wait()?;
+    Ok(())
+}
+
+/// the first changed file, relative to the project root, and how many more there are
+fn describe(changed: &[PathBuf], root: &Path) -> String {
+    let first = changed
+        .first()
+        .map(|path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        })
+        .unwrap_or_default();
+    match changed.len() {
+        0 | 1 => first,
+        n => format!("{} and {} more", first, n - 1),
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use anyhow::{anyhow, Error};
+use toml::{Table, Value};
+
+use crate::compile::{Diagnostics, MessageFormat, TARGET_MAIN};
+use crate::config::config;
+use crate::deploader::Classpath;
+use crate::error::JargoError;
+use crate::lockfile::Lockfile;
+use crate::manifest;
+use crate::project::{load_project, Artifact, Project, UpdatePolicy};
+
+/// The [workspace] table in the Jargo.toml in the root of a workspace
+#[derive(Debug, Clone)]
+pub(crate) struct WorkspaceConfig {
+    pub(crate) root: PathBuf,
+    /// the directories of the members
+    pub(crate) members: Vec<PathBuf>,
+    /// [workspace.dependencies], that members use with `workspace = true`
+    pub(crate) dependencies: Table,
+}
+
+impl WorkspaceConfig {
+    /// None when the Jargo.toml has no [workspace]
+    pub(crate) fn read(jargo_file: &Path) -> Result<Option<Self>, Error> {
+        let table = manifest::read(jargo_file)?;
+        let Some(workspace) = table.get("workspace") else {
+            return Ok(None);
+        };
+        Self::parse(jargo_file, workspace).map(Some).map_err(|e| {
+            JargoError::Manifest {
+                file: jargo_file.to_path_buf(),
+                key: Some("workspace".into()),
+                position: None,
+                message: e.to_string(),
+            }
+            .into()
+        })
+    }
+
+    fn parse(jargo_file: &Path, workspace: &Value) -> Result<Self, Error> {
+        let root = fs::canonicalize(parent_dir(jargo_file))?;
+        let invalid = || anyhow!("workspace.members should be a list of directories");
+        let mut members = vec![];
+        for member in workspace
+            .get("members")
+            .map(|m| m.as_array().ok_or_else(invalid))
+            .transpose()?
+            .into_iter()
+            .flatten()
+        {
+            let member = member.as_str().ok_or_else(invalid)?;
+            let dir = fs::canonicalize(root.join(member))
+                .map_err(|_| anyhow!("workspace member {} doesn't exist", member))?;
+            if !dir.join("Jargo.toml").exists() {
+                return Err(anyhow!("workspace member {} has no Jargo.toml", member));
+            }
+            members.push(dir);
+        }
+        let dependencies = match workspace.get("dependencies") {
+            Some(Value::Table(dependencies)) => dependencies.clone(),
+            Some(_) => return Err(anyhow!("workspace.dependencies should be a table")),
+            None => Table::new(),
+        };
+        Ok(Self {
+            root,
+            members,
+            dependencies,
+        })
+    }
+
+    /// The workspace of the project in this (canonical) directory: the [workspace] in its own
+    /// Jargo.toml, or the first one in a directory above it that has it as a member
+    pub(crate) fn find(project_dir: &Path) -> Result<Option<Self>, Error> {
+        for dir in project_dir.ancestors() {
+            let jargo_file = dir.join("Jargo.toml");
+            if !jargo_file.exists() {
+                continue;
+            }
+            if let Some(workspace) = Self::read(&jargo_file)? {
+                if dir == project_dir || workspace.members.iter().any(|m| m == project_dir) {
+                    return Ok(Some(workspace));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// all members share the target directory in the root, each in a directory of its own
+    pub(crate) fn target_dir(&self, project_dir: &Path) -> PathBuf {
+        let target = self.root.join("target");
+        match project_dir.strip_prefix(&self.root) {
+            Ok(member) if !member.as_os_str().is_empty() => target.join(member),
+            _ => target,
+        }
+    }
+}
+
+/// The result of building a project
+#[derive(Debug)]
+pub struct Build {
+    pub classpath: Classpath,
+    /// shared with the compilation of the tests, so that a warning isn't shown twice
+    pub diagnostics: Diagnostics,
+}
+
+/// The projects that are built together: the members of a workspace, or a single project,
+/// together with the projects they depend on through path dependencies
+#[derive(Debug)]
+pub struct Workspace {
+    /// where Jargo.lock is written
+    pub root: PathBuf,
+    /// every project comes after the projects it depends on
+    pub projects: Vec<Project>,
+    /// the projects that the command is for, the others are only built
+    selected: Vec<usize>,
+}
+
+/// loads the workspace of a Jargo.toml, with all members when it is the root of the workspace
+pub fn load_workspace(jargo_file: Option<&str>) -> Result<Workspace, Error> {
+    let jargo_file = Path::new(jargo_file.unwrap_or("./Jargo.toml"));
+    let is_package = manifest::read(jargo_file)?.contains_key("package");
+    let dir = fs::canonicalize(parent_dir(jargo_file))?;
+
+    let (root, start) = match WorkspaceConfig::read(jargo_file)? {
+        Some(workspace) => {
+            let mut start = vec![];
+            if is_package {
+                start.push(dir.clone());
+            }
+            start.extend(workspace.members);
+            (workspace.root, start)
+        }
+        None => {
+            let root = WorkspaceConfig::find(&dir)?
+                .map(|w| w.root)
+                .unwrap_or_else(|| dir.clone());
+            (root, vec![dir])
+        }
+    };
+    if start.is_empty() {
+        return Err(anyhow!("the workspace has no members"));
+    }
+
+    // the projects the start projects depend on, and the projects they depend on
+    let mut projects = vec![];
+    let mut index: HashMap<PathBuf, usize> = HashMap::new();
+    let mut queue: VecDeque<PathBuf> = start.iter().cloned().collect();
+    while let Some(dir) = queue.pop_front() {
+        if index.contains_key(&dir) {
+            continue;
+        }
+        let project = load_project(dir.join("Jargo.toml").to_str())?;
+        for dependency in project
+            .path_dependencies
+            .iter()
+            .chain(&project.test_path_dependencies)
+        {
+            queue.push_back(dependency.path.clone());
+        }
+        index.insert(dir, projects.len());
+        projects.push(project);
+    }
+    for project in &projects {
+        for dependency in project
+            .path_dependencies
+            .iter()
+            .chain(&project.test_path_dependencies)
+        {
+            let other = &projects[index[&dependency.path]];
+            if other.group != dependency.group || other.name != dependency.name {
+                return Err(anyhow!(
+                    "{}:{} in {} is {}:{}",
+                    dependency.group,
+                    dependency.name,
+                    dependency.path.display(),
+                    other.group,
+                    other.name
+                ));
+            }
+        }
+    }
+
+    let order = build_order(&projects, &index)?;
+    let selected: Vec<usize> = start
+        .iter()
+        .map(|dir| order.iter().position(|i| *i == index[dir]).unwrap_or(0))
+        .collect();
+    let mut projects: Vec<Option<Project>> = projects.into_iter().map(Some).collect();
+    let projects = order.iter().filter_map(|i| projects[*i].take()).collect();
+    Ok(Workspace {
+        root,
+        projects,
+        selected,
+    })
+}
+
+/// the directory of a Jargo.toml, also when it is given without one
+fn parent_dir(jargo_file: &Path) -> &Path {
+    match jargo_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// depth first, so that every project comes after the projects it depends on
+fn build_order(projects: &[Project], index: &HashMap<PathBuf, usize>) -> Result<Vec<usize>, Error> {
+    fn visit(
+        i: usize,
+        projects: &[Project],
+        index: &HashMap<PathBuf, usize>,
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), Error> {
+        if order.contains(&i) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|p| *p == i) {
+            let cycle: Vec<&str> = path[start..]
+                .iter()
+                .chain([&i])
+                .map(|p| projects[*p].name.as_str())
+                .collect();
+            return Err(anyhow!("cyclic path dependencies: {}", cycle.join(" -> ")));
+        }
+        path.push(i);
+        for dependency in projects[i]
+            .path_dependencies
+            .iter()
+            .chain(&projects[i].test_path_dependencies)
+        {
+            visit(index[&dependency.path], projects, index, path, order)?;
+        }
+        path.pop();
+        order.push(i);
+        Ok(())
+    }
+
+    let mut order = vec![];
+    for i in 0..projects.len() {
+        visit(i, projects, index, &mut vec![], &mut order)?;
+    }
+    Ok(order)
+}
+
+impl Workspace {
+    /// the projects that the command is for
+    pub fn selected(&self) -> impl Iterator<Item = (usize, &Project)> {
+        self.selected.iter().map(|i| (*i, &self.projects[*i]))
+    }
+
+    /// checks for new snapshots in every build, instead of following the update policies
+    pub fn refresh_snapshots(&mut self) {
+        for project in &mut self.projects {
+            for repo in &mut project.repositories {
+                repo.snapshots = UpdatePolicy::Always;
+            }
+        }
+    }
+
+    /// the project to run: the one with this name, or the only selected project
+    pub fn runnable(&self, member: Option<&str>) -> Result<usize, Error> {
+        match member {
+            Some(member) => self
+                .projects
+                .iter()
+                .position(|p| p.name == member)
+                .ok_or_else(|| anyhow!("no project {} in the workspace", member)),
+            None if self.selected.len() == 1 => Ok(self.selected[0]),
+            None => Err(anyhow!(
+                "choose one of {} with --member",
+                self.selected()
+                    .map(|(_, p)| p.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Resolves the dependencies of all projects, one after the other because they share the
+    /// local cache, records them in Jargo.lock and compiles the projects. Projects that don't
+    /// depend on each other are compiled in parallel.
+    ///
+    /// Returns the classpath of every project, in the order of the projects, with the
+    /// diagnostics that the compilation of its tests should continue with.
+    pub fn build(&self, message_format: MessageFormat) -> Result<Vec<Build>, Error> {
+        let mut classpaths = vec![];
+        for (i, project) in self.projects.iter().enumerate() {
+            let main = self.dependencies_of(i, false);
+            let test = self.dependencies_of(i, true);
+            let inherited = |projects: &[usize]| -> Vec<Artifact> {
+                projects
+                    .iter()
+                    .flat_map(|p| self.projects[*p].main_dependencies.iter().cloned())
+                    .collect()
+            };
+            let mut classpath =
+                crate::deploader::load_with(project, &inherited(&main), &inherited(&test))?;
+            // the classes of the other projects come before the jars
+            let classes = |projects: &[usize]| -> Vec<String> {
+                projects
+                    .iter()
+                    .map(|p| {
+                        Path::new(&self.projects[*p].target_dir)
+                            .join(TARGET_MAIN)
+                            .to_string_lossy()
+                            .to_string()
+                    })
+                    .collect()
+            };
+            classpath.main.splice(0..0, classes(&main));
+            classpath.test.splice(0..0, classes(&test));
+            classpaths.push(classpath);
+        }
+        let lockfile_path = self.root.join("Jargo.lock");
+        let mut lockfile = Lockfile::new(
+            classpaths.iter().flat_map(|c| c.artifacts.iter()),
+            classpaths.iter().flat_map(|c| c.signers.iter()),
+        );
+        if lockfile_path.exists() {
+            for (group, fingerprint, known) in lockfile.trust(&Lockfile::read(&lockfile_path)?) {
+                let key_changes = config()
+                    .signatures
+                    .as_ref()
+                    .map(|s| s.key_changes)
+                    .unwrap_or_default();
+                key_changes.apply(JargoError::KeyChanged {
+                    group,
+                    fingerprint,
+                    known,
+                })?;
+            }
+        }
+        lockfile.write(&lockfile_path)?;
+        crate::cache::record_lockfile(&lockfile_path)?;
+
+        let mut diagnostics: Vec<Diagnostics> = self
+            .projects
+            .iter()
+            .map(|_| Diagnostics::new(message_format))
+            .collect();
+        for level in self.levels() {
+            let failures: Vec<Error> = thread::scope(|scope| {
+                let builds: Vec<_> = diagnostics
+                    .iter_mut()
+                    .enumerate()
+                    .filter(|(i, _)| level.contains(i))
+                    .map(|(i, diagnostics)| {
+                        let (project, classpath) = (&self.projects[i], &classpaths[i]);
+                        scope.spawn(move || crate::compile::run(project, classpath, diagnostics))
+                    })
+                    .collect();
+                builds
+                    .into_iter()
+                    .filter_map(|build| match build.join() {
+                        Ok(result) => result.err(),
+                        Err(_) => Some(anyhow!("the build panicked")),
+                    })
+                    .collect()
+            });
+            if let Some(failure) = failures.into_iter().next() {
+                return Err(failure);
+            }
+        }
+        Ok(classpaths
+            .into_iter()
+            .zip(diagnostics)
+            .map(|(classpath, diagnostics)| Build {
+                classpath,
+                diagnostics,
+            })
+            .collect())
+    }
+
+    /// The projects that a project depends on, directly or through others, nearest first.
+    /// For the tests, these are the test path dependencies and what they depend on.
+    fn dependencies_of(&self, i: usize, test: bool) -> Vec<usize> {
+        // the projects are loaded from their canonical directory
+        let position = |path: &PathBuf| {
+            self.projects
+                .iter()
+                .position(|p| Path::new(&p.project_root) == path)
+        };
+        let direct = if test {
+            &self.projects[i].test_path_dependencies
+        } else {
+            &self.projects[i].path_dependencies
+        };
+        let mut dependencies = vec![];
+        let mut queue: VecDeque<usize> = direct.iter().filter_map(|d| position(&d.path)).collect();
+        while let Some(dependency) = queue.pop_front() {
+            if dependency == i || dependencies.contains(&dependency) {
+                continue;
+            }
+            dependencies.push(dependency);
+            queue.extend(
+                self.projects[dependency]
+                    .path_dependencies
+                    .iter()
+                    .filter_map(|d| position(&d.path)),
+            );
+        }
+        dependencies
+    }
+
+    /// the projects grouped so that every group only depends on the groups before it
+    fn levels(&self) -> Vec<Vec<usize>> {
+        let mut levels: Vec<usize> = vec![];
+        for i in 0..self.projects.len() {
+            // the projects are in build order, so the levels of the dependencies are known
+            let level = self
+                .dependencies_of(i, false)
+                .into_iter()
+                .chain(self.dependencies_of(i, true))
+                .map(|d| levels[d] + 1)
+                .max()
+                .unwrap_or(0);
+            levels.push(level);
+        }
+        let mut groups = vec![vec![]; levels.iter().max().map_or(0, |l| l + 1)];
+        for (i, level) in levels.into_iter().enumerate() {
+            groups[level].push(i);
+        }
+        groups
+    }
+}
@@ -4,10 +4,10 @@ import org.junit.Test;
 
 import static org.junit.Assert.assertEquals;
 
-public class SampleTests {
+public class SampleTest {
 
     @Test
-    public int getTheNumberTest() {
+    public void getTheNumberTest() {
        assertEquals(42, Sample.getTheNumber());
     }
-}
\ No newline at end of file
+}